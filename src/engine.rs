@@ -1,5 +1,8 @@
 pub mod graphics;
 pub mod event_loop;
 pub mod events;
+pub mod input;
 pub mod key_states;
-pub mod scenes;
\ No newline at end of file
+pub mod scenes;
+pub mod systems;
+pub mod window;
\ No newline at end of file
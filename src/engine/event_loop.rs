@@ -1,193 +1,672 @@
-use std::{path::Path, sync::{Arc, RwLock}};
+use std::{fs, path::Path, sync::{Arc, RwLock}};
 
-use glfw::{Action, Context, GlfwReceiver, Key, WindowEvent};
-use nalgebra::{Matrix4, Vector3};
+use glfw::{Context, CursorMode, GlfwReceiver, StandardCursor, WindowEvent};
+use nalgebra::Vector3;
 
-use crate::engine::{events::{collision, movement::rotate_object}, graphics};
+use crate::engine::{events::{event_bus::{EngineEvent, EventBus, EventKind}, movement::rotate_object}, graphics, input::{combo::ComboRecognizer, gamepad::{GamepadEvent, GamepadManager, GlfwJoystickProvider}, input_map::InputMap, input_recording::{InputRecorder, InputRecording, InputReplay, RecordedInput}, text_input::TextInput}, systems::{command_queue::{CommandQueue, EngineCommand}, engine_systems, scheduler::{Scheduler, Stage, SystemDescriptor}, system_context::SystemContext}};
 
-use super::{events::movement::move_object, graphics::{texture_manager::TextureManager, util::{master_clock, master_graphics_list::MasterGraphicsList}}, scenes::scene_manager::SceneManager, key_states::State};
+use super::{events::movement::move_object, graphics::{shader_manager::ShaderManager, texture_manager::TextureManager, util::{frame_limiter::FrameLimiter, master_clock, master_graphics_list::MasterGraphicsList}}, scenes::{background_loader::{BackgroundLoader, LoadProgress, LoadRequest}, scene_data::PrefabInstance, scene_manager::SceneManager, scene_migration, scene_stack::{SceneHooks, SceneTransition}, tiled_import::TiledImportOptions}, key_states::State, window::{cursor::CursorController, display::{self, DisplayController, MonitorInfo}, viewport::{ScalingPolicy, Viewport}, window_config::{DisplayMode, WindowConfig}, window_setup}};
 
 pub struct EventLoop {
     glfw: glfw::Glfw,
     window: glfw::PWindow,
     events: GlfwReceiver<(f64, WindowEvent)>,
     master_graphics_list: MasterGraphicsList,
+    texture_manager: Arc<RwLock<TextureManager>>,
+    shader_manager: Arc<RwLock<ShaderManager>>,
+    scene_manager: SceneManager,
+    loader: BackgroundLoader,
+    event_bus: EventBus,
+    commands: CommandQueue, // Requested by systems and event handlers, applied after each frame
+    scheduler: Scheduler,
+    input_map: InputMap,
+    gamepads: GamepadManager,
+    text_input: TextInput,
+    combos: ComboRecognizer,
+    recorder: Option<InputRecorder>,
+    replay: Option<InputReplay>, // Replaces live input while set
     master_clock: master_clock::MasterClock,
-    projection_matrix: Matrix4<f32>,
+    frame_limiter: FrameLimiter,
+    viewport: Viewport,
+    display: DisplayController,
+    cursor: CursorController,
     current_resolution_index: usize, // Index to track the current resolution
     resolutions: Vec<(u32, u32)>, // Vector to hold multiple resolution options
 }
 
 impl EventLoop {
-    pub fn new() -> Self {
+    pub fn new(window_config: WindowConfig) -> Self {
         let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
 
-        // Define multiple resolution options
-        let resolutions = vec![
-            (3840, 2160), // 2160p, bugged? Cannot test
-            (2560, 1440), // 1440p
-            (1920, 1080), // 1080p
-            (1280, 720),  // 720p
-            (640, 360),   // 360p
-        ];
+        // Create the window and its OpenGL context from the config
+        let (mut window, events) = window_setup::create_window(&mut glfw, &window_config);
 
-        let window_width = resolutions[3].0;
-        let window_height = resolutions[3].1;
+        let display = DisplayController::new(&mut glfw, &window, &window_config);
 
-        glfw.window_hint(glfw::WindowHint::Resizable(false));
+        let (resolutions, current_resolution_index) = Self::list_resolutions(&mut glfw, &window, display.get_monitor_index());
 
-        // Create a windowed mode window and its OpenGL context
-        let (mut window, events) = glfw
-            .create_window(window_width as u32, window_height as u32, "Rusted-OpenGL", glfw::WindowMode::Windowed)
-            .expect("Failed to create GLFW window.");
+        // Enable key events
+        window.set_key_polling(true);
 
-        // Set up the projection matrix once
-        let projection_matrix = Self::calculate_projection_matrix(window_width as f32, window_height as f32);
+        // Enable Unicode character events for text entry
+        window.set_char_polling(true);
 
-        // Make the window's context current
-        window.make_current();
+        // Enable file drop events, used to load dropped scenes and images
+        window.set_drag_and_drop_polling(true);
 
-        // Enable key events
-        window.set_key_polling(true);
+        // Enable mouse events
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_cursor_enter_polling(true);
+        window.set_scroll_polling(true);
+
+        // Enable window resize events, used to convert cursor positions to framebuffer pixels
+        window.set_size_polling(true);
+
+        // Enable framebuffer resize events, which report pixels rather than screen coordinates on HiDPI displays
+        window.set_framebuffer_size_polling(true);
 
         // Load OpenGL functions
         graphics::glfw::load_gl_symbols();
 
+        if window_config.msaa_samples.is_some() {
+            unsafe {
+                gl::Enable(gl::MULTISAMPLE);
+            }
+        }
+
+        // Blend by alpha so textures with transparency and fading scenes draw over what is behind them
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+
+        // Set up the viewport and projection matrix from the actual framebuffer size
+        let (virtual_width, virtual_height) = window_config.get_virtual_resolution();
+        let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
+        let mut viewport = Viewport::new(window_config.scaling_policy, virtual_width, virtual_height, framebuffer_width, framebuffer_height);
+        let (window_width, window_height) = window.get_size();
+        viewport.set_window_size(window_width, window_height);
+
+        // Register the engine's own systems
+        let mut scheduler = Scheduler::new();
+        scheduler.add_system(engine_systems::dispatch_events_system()).expect("Failed to register the event dispatch system");
+        scheduler.add_system(engine_systems::clear_screen_system(0.2, 0.3, 0.3, 1.0)).expect("Failed to register the clear screen system");
+        scheduler.add_system(engine_systems::draw_system()).expect("Failed to register the draw system");
+
         // Initialize the master graphics list
         let master_graphics_list = MasterGraphicsList::new();
 
         let master_clock = master_clock::MasterClock::new();
 
+        // The swap interval applies to the current context, so the window must be created first
+        let frame_limiter = FrameLimiter::new(window_config.swap_interval, window_config.max_fps);
+        frame_limiter.apply_swap_interval(&mut glfw);
+
+        // Gamepads are read through their own handle to the GLFW library
+        let gamepads = GamepadManager::new(Box::new(GlfwJoystickProvider::new(glfw.clone())));
+
         Self {
             glfw,
             window,
             events,
             master_graphics_list,
+            texture_manager: Arc::new(RwLock::new(TextureManager::new())),
+            shader_manager: Arc::new(RwLock::new(ShaderManager::new())),
+            scene_manager: SceneManager::new(),
+            loader: BackgroundLoader::new(),
+            event_bus: EventBus::new(),
+            commands: CommandQueue::new(),
+            scheduler,
+            input_map: InputMap::engine_defaults(),
+            gamepads,
+            text_input: TextInput::new(),
+            combos: ComboRecognizer::new(),
+            recorder: None,
+            replay: None,
             master_clock,
-            projection_matrix,
-            current_resolution_index: 3,
+            frame_limiter,
+            viewport,
+            display,
+            cursor: CursorController::new(),
+            current_resolution_index,
             resolutions,
         }
     }
 
-    fn calculate_projection_matrix(width: f32, height: f32) -> Matrix4<f32> {
-        let aspect_ratio = width / height;
-        Matrix4::new_orthographic(-1.0, 1.0, -1.0 / aspect_ratio, 1.0 / aspect_ratio, -1.0, 1.0)
+    // Build the resolution options from the monitor's video modes, making sure the current window size is one of them
+    fn list_resolutions(glfw: &mut glfw::Glfw, window: &glfw::PWindow, monitor_index: usize) -> (Vec<(u32, u32)>, usize) {
+        let mut resolutions = display::monitor_resolutions(glfw, monitor_index);
+
+        let (window_width, window_height) = window.get_size();
+        let window_resolution = (window_width as u32, window_height as u32);
+        if !resolutions.contains(&window_resolution) {
+            resolutions.push(window_resolution);
+            resolutions.sort_by(|a, b| b.cmp(a));
+        }
+        let current_resolution_index = resolutions.iter().position(|&r| r == window_resolution).unwrap();
+
+        (resolutions, current_resolution_index)
+    }
+
+    // Resize to one of the resolution options, the viewport follows through the FramebufferSize event
+    fn set_resolution(&mut self, index: usize) {
+        self.current_resolution_index = index;
+        let (width, height) = self.resolutions[index];
+        self.display.set_resolution(&mut self.glfw, &mut self.window, width, height);
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        self.display.toggle_fullscreen(&mut self.glfw, &mut self.window);
+    }
+
+    pub fn toggle_borderless(&mut self) {
+        self.display.toggle_borderless(&mut self.glfw, &mut self.window);
+    }
+
+    pub fn set_display_mode(&mut self, display_mode: DisplayMode) {
+        self.display.set_display_mode(&mut self.glfw, &mut self.window, display_mode);
+    }
+
+    /// Moves the window to another monitor and switches to that monitor's resolutions.
+    pub fn move_to_monitor(&mut self, monitor_index: usize) -> Result<(), String> {
+        self.display.move_to_monitor(&mut self.glfw, &mut self.window, monitor_index)?;
+        (self.resolutions, self.current_resolution_index) = Self::list_resolutions(&mut self.glfw, &self.window, monitor_index);
+        Ok(())
+    }
+
+    pub fn get_monitors(&mut self) -> Vec<MonitorInfo> {
+        display::enumerate_monitors(&mut self.glfw)
+    }
+
+    /// Loads a cursor image under a name for set_custom_cursor.
+    pub fn load_cursor_image(&mut self, name: &str, path: &str, hotspot_x: u32, hotspot_y: u32) -> Result<(), String> {
+        self.cursor.load_cursor_image(name, path, hotspot_x, hotspot_y)
+    }
+
+    pub fn set_custom_cursor(&mut self, name: &str) -> Result<(), String> {
+        self.cursor.set_custom_cursor(&mut self.window, name)
+    }
+
+    pub fn set_standard_cursor(&mut self, cursor: StandardCursor) {
+        self.cursor.set_standard_cursor(&mut self.window, cursor);
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.cursor.reset_cursor(&mut self.window);
+    }
+
+    /// Shows, hides or locks the cursor. While locked, read movement from State::get_cursor_delta.
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.cursor.set_cursor_mode(&self.glfw, &mut self.window, mode);
+    }
+
+    pub fn get_cursor(&self) -> &CursorController {
+        &self.cursor
+    }
+
+    pub fn get_texture_manager(&self) -> Arc<RwLock<TextureManager>> {
+        Arc::clone(&self.texture_manager)
+    }
+
+    pub fn get_shader_manager(&self) -> Arc<RwLock<ShaderManager>> {
+        Arc::clone(&self.shader_manager)
+    }
+
+    pub fn get_scene_manager(&mut self) -> &mut SceneManager {
+        &mut self.scene_manager
+    }
+
+    /// Loads textures and scenes without blocking the frame. Loaded scenes can be pushed once the progress is finished.
+    pub fn load_in_background(&mut self, request: LoadRequest) -> LoadProgress {
+        self.loader.start(request)
+    }
+
+    /// Creates a prefab instance in the world and returns the names of its objects.
+    pub fn spawn_prefab(&mut self, instance: &PrefabInstance) -> Result<Vec<String>, String> {
+        self.scene_manager.spawn_prefab(instance, &self.master_graphics_list, &mut self.event_bus, &self.texture_manager.read().unwrap(), &self.shader_manager.read().unwrap())
+    }
+
+    /// Puts a loaded scene on top of the scene stack.
+    pub fn push_scene(&mut self, name: &str, transition: SceneTransition) -> Result<(), String> {
+        self.scene_manager.push_scene(name, transition, &self.master_graphics_list, &mut self.event_bus)
+    }
+
+    /// Takes the top scene off the scene stack and returns its name.
+    pub fn pop_scene(&mut self, transition: SceneTransition) -> Option<String> {
+        self.scene_manager.pop_scene(transition, &self.master_graphics_list, &mut self.event_bus)
+    }
+
+    /// Swaps the top scene of the scene stack for another loaded scene.
+    pub fn replace_scene(&mut self, name: &str, transition: SceneTransition) -> Result<(), String> {
+        self.scene_manager.replace_scene(name, transition, &self.master_graphics_list, &mut self.event_bus)
+    }
+
+    /// Removes a scene and only its objects from the world and forgets it.
+    pub fn unload_scene(&mut self, name: &str) -> Result<(), String> {
+        self.scene_manager.unload_scene(name, &self.master_graphics_list, &mut self.event_bus)
+    }
+
+    pub fn set_scene_hooks(&mut self, name: &str, hooks: SceneHooks) {
+        self.scene_manager.set_scene_hooks(name, hooks);
+    }
+
+    pub fn get_event_bus(&mut self) -> &mut EventBus {
+        &mut self.event_bus
+    }
+
+    /// A handle to the command queue, for event bus handlers and other code that runs while the loop holds the engine.
+    pub fn get_commands(&self) -> CommandQueue {
+        self.commands.clone()
+    }
+
+    pub fn set_input_map(&mut self, input_map: InputMap) {
+        self.input_map = input_map;
+    }
+
+    pub fn get_input_map(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+
+    pub fn get_gamepads(&mut self) -> &mut GamepadManager {
+        &mut self.gamepads
+    }
+
+    pub fn set_combos(&mut self, combos: ComboRecognizer) {
+        self.combos = combos;
+    }
+
+    pub fn get_combos(&mut self) -> &mut ComboRecognizer {
+        &mut self.combos
+    }
+
+    pub fn get_text_input(&mut self) -> &mut TextInput {
+        &mut self.text_input
+    }
+
+    /// Starts recording input from the next frame, discarding any recording in progress.
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(InputRecorder::new());
+    }
+
+    /// Stops recording and returns what was recorded, or None if nothing was being recorded.
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.take().map(|recorder| recorder.finish())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Replays a recording from the next frame. Live input is ignored until the replay finishes,
+    /// and each frame uses the recorded delta time so the simulation matches the original run.
+    /// Keys and mouse buttons still held when it ends are released.
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.replay = Some(InputReplay::new(recording));
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn get_scheduler(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
+    pub fn set_vsync(&mut self, enabled: bool) {
+        self.frame_limiter.set_vsync(&mut self.glfw, enabled);
+    }
+
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.frame_limiter.set_max_fps(max_fps);
+    }
+
+    pub fn set_scaling_policy(&mut self, policy: ScalingPolicy) {
+        self.viewport.set_policy(policy);
     }
     
+    // Route one window event to the rebind capture, viewport, input state and event bus
+    fn handle_window_event(&mut self, event: WindowEvent, state: &mut State) {
+        // A pending rebind takes the next press before gameplay sees it
+        if self.input_map.capture_input(&event) {
+            return;
+        }
+
+        // Typing into an active text input must not also move the player or trigger hotkeys
+        let typed = if self.text_input.is_active() && TextInput::is_paste_shortcut(&event) {
+            // A replay delivers the pasted text as its own input instead of reading the live clipboard
+            if self.replay.is_none() {
+                if let Some(text) = self.window.get_clipboard_string() {
+                    self.paste_text(&text);
+                }
+            }
+            true
+        } else {
+            self.text_input.handle_event(&event)
+        };
+        if typed {
+            return;
+        }
+
+        match &event {
+            glfw::WindowEvent::Size(width, height) => {
+                self.viewport.set_window_size(*width, *height);
+            },
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                self.viewport.resize(*width, *height);
+                self.event_bus.publish(EngineEvent::WindowResized { width: *width, height: *height });
+            },
+            glfw::WindowEvent::FileDrop(paths) => {
+                self.event_bus.publish(EngineEvent::FilesDropped(paths.clone()));
+                for path in paths {
+                    if let Err(e) = self.load_dropped_file(path) {
+                        println!("Could not load dropped file '{}': {}", path.display(), e);
+                    }
+                }
+            },
+            _ => {
+                state.handle_event(event.clone()); // Handle other window events
+            }
+        }
+        self.event_bus.publish(EngineEvent::Input(event));
+    }
+
+    // Load a dropped scene or Tiled map into the world, or a dropped image as a texture named after the file.
+    // A scene file or map that is already loaded is reloaded in place, any other scene with the same name is refused.
+    fn load_dropped_file(&mut self, path: &Path) -> Result<(), String> {
+        let path_str = path.to_str().ok_or("Path is not valid UTF-8")?;
+        let name = path.file_stem().and_then(|name| name.to_str()).ok_or("Path has no file name")?;
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase();
+
+        match extension.as_str() {
+            "json" | "tmj" | "tmx" => {
+                let data = if extension == "json" {
+                    let data = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path_str, e))?;
+                    let document: serde_json::Value = serde_json::from_str(&data).map_err(|e| format!("Failed to parse '{}': {}", path_str, e))?;
+                    if !scene_migration::is_scene_document(&document) {
+                        return Err(format!("'{}' is not a scene file", path_str));
+                    }
+                    Some(data)
+                } else {
+                    None
+                };
+
+                if self.scene_manager.get_scene(name).is_some() && !self.scene_manager.is_loaded_from(name, path_str) {
+                    return Err(format!("A different scene named '{}' is already loaded", name));
+                }
+                let result = {
+                    let (texture_manager, shader_manager) = (&self.texture_manager.read().unwrap(), &self.shader_manager.read().unwrap());
+                    if self.scene_manager.get_scene(name).is_some() {
+                        self.scene_manager.reload_scene(name, &self.master_graphics_list, &mut self.event_bus, texture_manager, shader_manager)
+                    } else if let Some(data) = data {
+                        self.scene_manager.load_scene_from_str(path_str, &data, texture_manager, shader_manager)
+                    } else {
+                        self.scene_manager.load_tiled_map(path_str, &TiledImportOptions::default(), texture_manager, shader_manager)
+                    }
+                };
+                result.map_err(|e| e.to_string())?;
+
+                if self.scene_manager.get_scene_stack().iter().any(|scene| scene == name) {
+                    return Ok(());
+                }
+                self.push_scene(name, SceneTransition::Cut)
+            },
+            "png" | "jpg" | "jpeg" => {
+                self.texture_manager.read().unwrap().load_texture(name, path_str)?;
+                Ok(())
+            },
+            _ => Err(format!("Unsupported file type '{}'", extension)),
+        }
+    }
+
+    // Carry out the commands queued during the frame, in the order they were queued
+    fn apply_commands(&mut self) {
+        for command in self.commands.drain() {
+            let result = match command {
+                EngineCommand::PushScene { name, transition } => self.push_scene(&name, transition),
+                EngineCommand::PopScene { transition } => {
+                    self.pop_scene(transition).map(|_| ()).ok_or_else(|| "The scene stack is empty".to_string())
+                },
+                EngineCommand::ReplaceScene { name, transition } => self.replace_scene(&name, transition),
+                EngineCommand::UnloadScene(name) => self.unload_scene(&name),
+                EngineCommand::SpawnPrefab(instance) => self.spawn_prefab(&instance).map(|_| ()),
+                EngineCommand::LoadInBackground { request, progress } => {
+                    self.loader.start_with_progress(request, progress);
+                    Ok(())
+                },
+                EngineCommand::SetCursorMode(mode) => {
+                    self.set_cursor_mode(mode);
+                    Ok(())
+                },
+                EngineCommand::StartRebind { action, replace } => {
+                    self.input_map.start_rebind(&action, replace);
+                    Ok(())
+                },
+                EngineCommand::StartAxisRebind { axis, positive, replace } => {
+                    self.input_map.start_axis_rebind(&axis, positive, replace);
+                    Ok(())
+                },
+                EngineCommand::CancelRebind => {
+                    self.input_map.cancel_rebind();
+                    Ok(())
+                },
+                EngineCommand::SetTextInputActive(active) => {
+                    self.text_input.set_active(active);
+                    Ok(())
+                },
+            };
+            if let Err(e) = result {
+                println!("Engine command failed: {}", e);
+            }
+        }
+    }
+
+    // Paste into the text input, recording the text since the clipboard is not part of a replay
+    fn paste_text(&mut self, text: &str) {
+        self.text_input.paste(text);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_paste(text);
+        }
+    }
+
     pub fn run_event_loop(&mut self) {  
         // Create the state to manage keys and other state
         let mut state = State::new();
 
-        let texture_manager = Arc::new(RwLock::new(TextureManager::new()));
-
-        let _ = texture_manager.write().unwrap().load_textures_from_directory("src\\resources\\textures");
+        if let Err(e) = self.texture_manager.write().unwrap().load_textures_from_directory("./src/resources/textures") {
+            println!("Failed to load textures: {}", e);
+        }
+        if let Err(e) = self.shader_manager.read().unwrap().load_shaders_from_directory("./src/resources/shaders") {
+            println!("Failed to load shaders: {}", e);
+        }
+        if let Err(e) = self.scene_manager.load_prefabs_from_directory("./src/resources/prefabs", &self.texture_manager.read().unwrap(), &self.shader_manager.read().unwrap()) {
+            println!("Failed to load prefabs: {}", e);
+        }
 
-        let mut scene_manager = SceneManager::new();
+        // Reload edited scene files while developing
+        if cfg!(debug_assertions) {
+            self.scene_manager.set_hot_reload_interval(Some(1.0));
+        }
 
         let path = Path::new("./src/resources/scenes/testscene2.json");
-        let _ = scene_manager.load_scene_from_json(path.to_str().unwrap(), &texture_manager.read().unwrap());
+        if let Err(e) = self.scene_manager.load_scene_from_json(path.to_str().unwrap(), &self.texture_manager.read().unwrap(), &self.shader_manager.read().unwrap()) {
+            println!("Failed to load scene '{}': {}", path.display(), e);
+        }
 
-        if let Some(scene) = scene_manager.get_scene("testscene2") {
-            let scene = scene.write().expect("Failed to lock the scene for writing");
-            self.master_graphics_list.load_scene(&scene);
-        } else {
-            println!("Scene 'testscene2' not found");
+        if let Err(e) = self.push_scene("testscene2", SceneTransition::Fade { duration: 0.5 }) {
+            println!("Failed to show scene: {}", e);
         }
         
-        while !self.window.should_close() {
-            // Update the clock
-            self.master_clock.update();
-    
-            self.glfw.poll_events();
-            for (_, event) in glfw::flush_messages(&self.events) {
-                match event {
-                    glfw::WindowEvent::Key(Key::KpSubtract, _, Action::Press, _) => {
-                        if  self.current_resolution_index<self.resolutions.capacity()-1 {
-                            self.current_resolution_index += 1;
-                            self.window.set_size(self.resolutions[self.current_resolution_index].0 as i32, self.resolutions[self.current_resolution_index].1 as i32);
-                            self.projection_matrix = Self::calculate_projection_matrix(self.resolutions[self.current_resolution_index].0 as f32, self.resolutions[self.current_resolution_index].1 as f32);
-                            unsafe {
-                                gl::Viewport(0, 0, self.resolutions[self.current_resolution_index].0 as i32, self.resolutions[self.current_resolution_index].1 as i32);  // Update the OpenGL viewport
-                            }
-                        }
-                    },
-                    glfw::WindowEvent::Key(Key::KpAdd, _, Action::Press, _) => {
-                        if  self.current_resolution_index>0 {
-                            self.current_resolution_index -= 1;
-                            self.window.set_size(self.resolutions[self.current_resolution_index].0 as i32, self.resolutions[self.current_resolution_index].1 as i32);
-                            self.projection_matrix = Self::calculate_projection_matrix(self.resolutions[self.current_resolution_index].0 as f32, self.resolutions[self.current_resolution_index].1 as f32);
-                            unsafe {
-                                gl::Viewport(0, 0, self.resolutions[self.current_resolution_index].0 as i32, self.resolutions[self.current_resolution_index].1 as i32);  // Update the OpenGL viewport
-                            }
-                        }
-                    },
-                    _ => {
-                        state.handle_key_event(event); // Handle other window events
-                    }
-                }
+        // Report collisions with the player
+        self.event_bus.subscribe(EventKind::Collision, |event| {
+            if let EngineEvent::Collision(collision) = event {
+                println!("Collision detected between Object ID {} and Object ID {}", collision.object_name_1, collision.object_name_2);
             }
-    
-            // Retrieve the square from the master graphics list
-            let square = self.master_graphics_list.get_object("debug_playersquare").expect("Object not found");
+        });
 
-            let delta_time = self.master_clock.get_delta_time();
+        self.scheduler.add_system(engine_systems::collision_system("debug_playersquare")).expect("Failed to register the collision system");
+
+        // Apply movement based on active actions
+        self.scheduler.add_system(SystemDescriptor::new("player_movement", Stage::Update, |context| {
+            // The square goes away with its scene, when it is unloaded, popped or edited out of the file
+            let Some(square) = context.master_graphics_list.get_object("debug_playersquare") else {
+                return;
+            };
+            let state = context.state;
+            let input_map = context.input_map;
+            let delta_time = context.delta_time;
 
-            // Apply movement based on active keys
             let move_speed = 0.2;
             let rotation_speed = 2.0;
-            if state.is_key_pressed(Key::W) {
-                move_object(square.clone(), Vector3::new(0.0, 1.0, 0.0), move_speed, delta_time);
-            }
-            if state.is_key_pressed(Key::S) {
-                move_object(square.clone(), Vector3::new(0.0, -1.0, 0.0), move_speed, delta_time);
+            let direction = Vector3::new(input_map.get_axis("move_x", state), input_map.get_axis("move_y", state), 0.0);
+            if direction != Vector3::zeros() {
+                move_object(square.clone(), direction, move_speed, delta_time);
             }
-            if state.is_key_pressed(Key::A) {
-                move_object(square.clone(), Vector3::new(-1.0, 0.0, 0.0), move_speed, delta_time);
-            }
-            if state.is_key_pressed(Key::D) {
-                move_object(square.clone(), Vector3::new(1.0, 0.0, 0.0), move_speed, delta_time);
-            }
-            if state.is_key_pressed(Key::Q) {
+            if input_map.is_action_pressed("rotate_left", state) {
                 rotate_object(square.clone(), rotation_speed*delta_time);
             }
-            if state.is_key_pressed(Key::E) {
+            if input_map.is_action_pressed("rotate_right", state) {
                 rotate_object(square.clone(), -rotation_speed*delta_time);
             }
+        })).expect("Failed to register the player movement system");
 
-            //position debug info
-            //let debugpos = square.read().unwrap().get_position();
-            //println!("{}", debugpos);
-
-            //spin this object for testing
-            if let Some(object_2) = self.master_graphics_list.get_object("testscene2_obj1") {
+        //spin this object for testing
+        self.scheduler.add_system(SystemDescriptor::new("spin_test_object", Stage::Update, |context| {
+            if let Some(object_2) = context.master_graphics_list.get_object("testscene2_obj1") {
                 let mut object_2_read = object_2.write().unwrap(); // Read the `newsquare` object
-                let rotfactor = object_2_read.get_rotation()+1.0*delta_time;
+                let rotfactor = object_2_read.get_rotation()+1.0*context.delta_time;
                 object_2_read.set_rotation(rotfactor);
             } else {
                 println!("No object found with name testscene2_obj1.");
             }
+        })).expect("Failed to register the spin system");
 
-            // Call the collision checking method
-            let collision_events = collision::check_collisions(&self.master_graphics_list, "debug_playersquare");
+        while !self.window.should_close() {
+            // Update the clock
+            self.master_clock.update();
 
-            for event in collision_events {
-                println!("Collision detected between Object ID {} and Object ID {}", event.object_name_1, event.object_name_2);
+            // Take this frame's recorded input and timing when replaying
+            let replay_frame = self.replay.as_mut().and_then(|replay| replay.next_frame());
+            let finished_replay = if replay_frame.is_none() { self.replay.take() } else { None };
+            if let Some(frame) = &replay_frame {
+                self.master_clock.set_delta_time(frame.delta_time);
             }
+            let delta_time = self.master_clock.get_delta_time();
 
+            // Clear last frame's input transitions before collecting new events
+            state.begin_frame(delta_time);
 
-            // Render here
-            unsafe {
-                gl::ClearColor(0.2, 0.3, 0.3, 1.0); // Set background color
-                gl::Clear(gl::COLOR_BUFFER_BIT);    // Clear the screen
+            if let Some(replay) = finished_replay {
+                // The replay just finished, release what it left held and drop gamepads that only existed in the recording
+                for event in replay.release_events(&state) {
+                    self.handle_window_event(event, &mut state);
+                }
+                let live_gamepads = self.gamepads.get_connected_gamepads();
+                for index in state.get_connected_gamepads() {
+                    if !live_gamepads.contains(&index) {
+                        state.remove_gamepad(index);
+                    }
+                }
+            }
+
+            if let Some(recorder) = &mut self.recorder {
+                recorder.begin_frame(self.master_clock.get_frame_count(), delta_time);
+            }
+
+            self.glfw.poll_events();
+            // Collect first so handlers can borrow self mutably
+            let events: Vec<_> = glfw::flush_messages(&self.events).collect();
+            for (_, event) in events {
+                // Live input is ignored during a replay, window events still apply
+                if replay_frame.is_some() && RecordedInput::from_window_event(&event).is_some() {
+                    continue;
+                }
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record_event(&event);
+                }
+                self.handle_window_event(event, &mut state);
+            }
+
+            if let Some(frame) = replay_frame {
+                for input in &frame.inputs {
+                    if InputReplay::apply_gamepad_input(input, &mut state) {
+                        continue;
+                    }
+                    if let RecordedInput::Paste { text } = input {
+                        self.paste_text(text);
+                    } else if let Some(event) = input.to_window_event() {
+                        self.handle_window_event(event, &mut state);
+                    }
+                }
+            } else {
+                // Poll gamepads after window events so both are current for this frame
+                for gamepad_event in self.gamepads.update(&mut state) {
+                    match gamepad_event {
+                        GamepadEvent::Connected { index, name } => self.event_bus.publish(EngineEvent::GamepadConnected { index, name }),
+                        GamepadEvent::Disconnected { index } => self.event_bus.publish(EngineEvent::GamepadDisconnected(index)),
+                    }
+                }
+            }
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record_gamepads(&state);
+            }
+            self.input_map.capture_gamepad_input(&state);
+
+            // Match combos once all of this frame's input is in
+            self.combos.update(&state, self.master_clock.get_elapsed_time());
+            for name in self.combos.get_triggered() {
+                self.event_bus.publish(EngineEvent::ComboTriggered(name));
+            }
+
+            // Window hotkeys
+            if self.input_map.is_action_just_pressed("resolution_down", &state) && self.current_resolution_index < self.resolutions.len() - 1 {
+                self.set_resolution(self.current_resolution_index + 1);
+            }
+            if self.input_map.is_action_just_pressed("resolution_up", &state) && self.current_resolution_index > 0 {
+                self.set_resolution(self.current_resolution_index - 1);
+            }
+            if self.input_map.is_action_just_pressed("toggle_fullscreen", &state) {
+                self.toggle_fullscreen();
+            }
+            if self.input_map.is_action_just_pressed("toggle_borderless", &state) {
+                self.toggle_borderless();
             }
     
-            // Draw
-            self.master_graphics_list.draw_all(&self.projection_matrix);
+            // Finish anything the background loader has read, GL uploads must happen on this thread
+            self.loader.update(&self.texture_manager.read().unwrap(), &self.shader_manager.read().unwrap(), &mut self.scene_manager);
+
+            // Pick up edits to scene files
+            for error in self.scene_manager.poll_scene_files(delta_time, &self.master_graphics_list, &mut self.event_bus, &self.texture_manager.read().unwrap(), &self.shader_manager.read().unwrap()) {
+                println!("{}", error);
+            }
+
+            // Fade scenes in and out before systems see the world
+            self.scene_manager.update_transition(delta_time, &self.master_graphics_list, &mut self.event_bus);
+    
+            // Run the update and render stages
+            let mut context = SystemContext {
+                master_graphics_list: &self.master_graphics_list,
+                state: &state,
+                input_map: &self.input_map,
+                text_input: &self.text_input,
+                combos: &self.combos,
+                event_bus: &mut self.event_bus,
+                viewport: &self.viewport,
+                commands: &self.commands,
+                delta_time,
+            };
+            self.scheduler.run_frame(&mut context);
+
+            // Apply what systems and event handlers asked for now that nothing else holds the engine
+            self.apply_commands();
     
             // Swap buffers
             self.window.swap_buffers();
+
+            // Sleep off the rest of the frame if a frame cap is set
+            self.frame_limiter.wait_for_next_frame();
         }
-        self.master_graphics_list.remove_all();
+        self.scene_manager.clear_scene_stack(&self.master_graphics_list, &mut self.event_bus);
+        self.event_bus.dispatch();
     }
 }
\ No newline at end of file
@@ -1,2 +1,3 @@
 pub mod movement;
-pub mod collision;
\ No newline at end of file
+pub mod collision;
+pub mod event_bus;
\ No newline at end of file
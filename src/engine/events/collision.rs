@@ -1,6 +1,6 @@
 use crate::engine::graphics::util::master_graphics_list::MasterGraphicsList;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CollisionEvent {
     pub object_name_1: String,
     pub object_name_2: String,
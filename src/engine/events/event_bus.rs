@@ -0,0 +1,202 @@
+use std::{any::Any, fmt, path::PathBuf, sync::Arc};
+
+use glfw::WindowEvent;
+
+use super::collision::CollisionEvent;
+
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    Input(WindowEvent),
+    Collision(CollisionEvent),
+    SceneLoaded(String),
+    SceneUnloaded(String),
+    SceneReloaded(String), // A scene's file changed and its live objects were updated
+    WindowResized { width: i32, height: i32 }, // Framebuffer size in pixels
+    ObjectSpawned(String),
+    ObjectDespawned(String),
+    GamepadConnected { index: usize, name: String },
+    GamepadDisconnected(usize),
+    ComboTriggered(String),
+    FilesDropped(Vec<PathBuf>),
+    Custom(CustomEvent),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    Input,
+    Collision,
+    SceneLoaded,
+    SceneUnloaded,
+    SceneReloaded,
+    WindowResized,
+    ObjectSpawned,
+    ObjectDespawned,
+    GamepadConnected,
+    GamepadDisconnected,
+    ComboTriggered,
+    FilesDropped,
+    Custom,
+}
+
+impl EngineEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            EngineEvent::Input(_) => EventKind::Input,
+            EngineEvent::Collision(_) => EventKind::Collision,
+            EngineEvent::SceneLoaded(_) => EventKind::SceneLoaded,
+            EngineEvent::SceneUnloaded(_) => EventKind::SceneUnloaded,
+            EngineEvent::SceneReloaded(_) => EventKind::SceneReloaded,
+            EngineEvent::WindowResized { .. } => EventKind::WindowResized,
+            EngineEvent::ObjectSpawned(_) => EventKind::ObjectSpawned,
+            EngineEvent::ObjectDespawned(_) => EventKind::ObjectDespawned,
+            EngineEvent::GamepadConnected { .. } => EventKind::GamepadConnected,
+            EngineEvent::GamepadDisconnected(_) => EventKind::GamepadDisconnected,
+            EngineEvent::ComboTriggered(_) => EventKind::ComboTriggered,
+            EngineEvent::FilesDropped(_) => EventKind::FilesDropped,
+            EngineEvent::Custom(_) => EventKind::Custom,
+        }
+    }
+}
+
+/// A user defined event carrying an arbitrary payload, identified by name.
+#[derive(Clone)]
+pub struct CustomEvent {
+    name: String,
+    payload: Arc<dyn Any + Send + Sync>,
+}
+
+impl CustomEvent {
+    pub fn new<T: Any + Send + Sync>(name: &str, payload: T) -> Self {
+        Self {
+            name: name.to_string(),
+            payload: Arc::new(payload),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the payload if it is of type T.
+    pub fn get_payload<T: Any>(&self) -> Option<&T> {
+        self.payload.downcast_ref::<T>()
+    }
+}
+
+impl fmt::Debug for CustomEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomEvent").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+pub type SubscriptionId = usize;
+
+struct Subscriber {
+    id: SubscriptionId,
+    kind: Option<EventKind>, // None receives every event
+    priority: i32, // Lower priorities are called first
+    handler: Box<dyn FnMut(&EngineEvent)>,
+}
+
+/// Queues engine events during a frame and dispatches them to subscribers in order.
+pub struct EventBus {
+    subscribers: Vec<Subscriber>,
+    next_subscription_id: SubscriptionId,
+    pending_events: Vec<EngineEvent>, // Published this frame, waiting for dispatch
+    frame_events: Vec<EngineEvent>, // Dispatched by the last call to dispatch
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            subscribers: Vec::new(),
+            next_subscription_id: 0,
+            pending_events: Vec::new(),
+            frame_events: Vec::new(),
+        }
+    }
+
+    /// Subscribes a handler to one kind of event.
+    pub fn subscribe<F>(&mut self, kind: EventKind, handler: F) -> SubscriptionId
+    where
+        F: FnMut(&EngineEvent) + 'static,
+    {
+        self.add_subscriber(Some(kind), 0, Box::new(handler))
+    }
+
+    /// Subscribes a handler to every event.
+    pub fn subscribe_all<F>(&mut self, handler: F) -> SubscriptionId
+    where
+        F: FnMut(&EngineEvent) + 'static,
+    {
+        self.add_subscriber(None, 0, Box::new(handler))
+    }
+
+    /// Subscribes a handler with an explicit priority. Handlers with lower priorities run first,
+    /// handlers with equal priorities run in the order they subscribed.
+    pub fn subscribe_with_priority<F>(&mut self, kind: Option<EventKind>, priority: i32, handler: F) -> SubscriptionId
+    where
+        F: FnMut(&EngineEvent) + 'static,
+    {
+        self.add_subscriber(kind, priority, Box::new(handler))
+    }
+
+    fn add_subscriber(&mut self, kind: Option<EventKind>, priority: i32, handler: Box<dyn FnMut(&EngineEvent)>) -> SubscriptionId {
+        let id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+
+        // Insert after every subscriber with a lower or equal priority to keep the list ordered
+        let index = self.subscribers.partition_point(|subscriber| subscriber.priority <= priority);
+        self.subscribers.insert(index, Subscriber { id, kind, priority, handler });
+
+        id
+    }
+
+    /// Removes a subscription. Returns false if it did not exist.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count = self.subscribers.len();
+        self.subscribers.retain(|subscriber| subscriber.id != id);
+        self.subscribers.len() != count
+    }
+
+    /// Queues an event for the next dispatch.
+    pub fn publish(&mut self, event: EngineEvent) {
+        self.pending_events.push(event);
+    }
+
+    /// Delivers all queued events to subscribers in the order they were published.
+    /// The events stay readable through get_frame_events until the next dispatch.
+    pub fn dispatch(&mut self) {
+        self.frame_events = std::mem::take(&mut self.pending_events);
+
+        for event in &self.frame_events {
+            let kind = event.kind();
+            for subscriber in self.subscribers.iter_mut() {
+                if subscriber.kind.is_none_or(|subscribed| subscribed == kind) {
+                    (subscriber.handler)(event);
+                }
+            }
+        }
+    }
+
+    /// Returns the events delivered by the last dispatch.
+    pub fn get_frame_events(&self) -> &Vec<EngineEvent> {
+        &self.frame_events
+    }
+
+    /// Returns the events of one kind delivered by the last dispatch.
+    pub fn read_events(&self, kind: EventKind) -> impl Iterator<Item = &EngineEvent> {
+        self.frame_events.iter().filter(move |event| event.kind() == kind)
+    }
+
+    /// Drops queued events without delivering them.
+    pub fn clear(&mut self) {
+        self.pending_events.clear();
+    }
+}
@@ -1,5 +1,8 @@
 pub mod glfw;
 pub mod internal_object;
 pub mod util;
+pub mod shader_manager;
 pub mod texture_manager;
-mod compile;
\ No newline at end of file
+mod compile;
+#[cfg(test)]
+pub mod test_gl;
@@ -1,6 +1,6 @@
-use gl::types::GLuint;
+use gl::types::{GLenum, GLuint};
 use nalgebra::{Matrix4, Vector3};
-use std::{collections::HashSet, ffi::CString, sync::{Arc, RwLock}};
+use std::{collections::{HashMap, HashSet}, ffi::CString, sync::{Arc, RwLock}};
 use super::{vao::VAO, vbo::VBO};
 
 pub struct Generic2DGraphicsObject {
@@ -16,6 +16,12 @@ pub struct Generic2DGraphicsObject {
     scale: f32,
     model_matrix: Matrix4<f32>,
     collision_modes: HashSet<CollisionMode>,
+    texture_name: Option<String>, // Name the texture was loaded under, kept so the object can be saved
+    shader_name: Option<String>, // Name of the shader in the shader library, kept so the object can be saved
+    properties: HashMap<String, serde_json::Value>, // Custom values from scene files, not used by the engine
+    opacity: f32, // Multiplied into the alpha by shaders with an opacity uniform
+    draw_mode: DrawMode,
+    visible: bool, // Hidden objects are skipped when drawing but still collide
 }
 
 impl Clone for Generic2DGraphicsObject {
@@ -33,6 +39,12 @@ impl Clone for Generic2DGraphicsObject {
             scale: self.scale,
             model_matrix: self.model_matrix,
             collision_modes: self.collision_modes.clone(),
+            texture_name: self.texture_name.clone(),
+            shader_name: self.shader_name.clone(),
+            properties: self.properties.clone(),
+            opacity: self.opacity,
+            draw_mode: self.draw_mode,
+            visible: self.visible,
         }
     }
 }
@@ -64,6 +76,12 @@ impl Generic2DGraphicsObject {
             scale,
             model_matrix: Matrix4::identity(), // Identity matrix for 2D
             collision_modes,
+            texture_name: None,
+            shader_name: None,
+            properties: HashMap::new(),
+            opacity: 1.0,
+            draw_mode: DrawMode::TriangleFan,
+            visible: true,
         };
         object.initialize(texture_id); // Pass texture ID to initialize
         object
@@ -111,6 +129,12 @@ impl Generic2DGraphicsObject {
             let model_location = gl::GetUniformLocation(self.shader_program, CString::new("model").unwrap().as_ptr());
             let model_array: [f32; 16] = self.model_matrix.as_slice().try_into().expect("Matrix conversion failed");
             gl::UniformMatrix4fv(model_location, 1, gl::FALSE, model_array.as_ptr());
+
+            // Set the opacity, shaders without the uniform ignore it
+            let opacity_location = gl::GetUniformLocation(self.shader_program, CString::new("opacity").unwrap().as_ptr());
+            if opacity_location != -1 {
+                gl::Uniform1f(opacity_location, self.opacity);
+            }
         }
     }
 
@@ -120,7 +144,7 @@ impl Generic2DGraphicsObject {
             let vao = self.vao.read().unwrap(); // Lock the RwLock for read access
             vao.bind();
             // Draw elements based on the number of vertices
-            gl::DrawArrays(gl::TRIANGLE_FAN, 0, (self.vertex_data.len() / 2) as i32);
+            gl::DrawArrays(self.draw_mode.gl_mode(), 0, (self.vertex_data.len() / 2) as i32);
             VAO::unbind();
         }
     }
@@ -228,6 +252,11 @@ impl Generic2DGraphicsObject {
         &self.name
     }
 
+    // Objects in the master graphics list are keyed by name, so only rename objects that are not in it
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     pub fn set_position(&mut self, position: nalgebra::Vector3<f32>) {
         self.position = position;
     }
@@ -256,6 +285,95 @@ impl Generic2DGraphicsObject {
         self.scale
     }
 
+    pub fn get_vertex_data(&self) -> &[f32] {
+        &self.vertex_data
+    }
+
+    pub fn get_texture_coords(&self) -> &[f32] {
+        &self.texture_coords
+    }
+
+    pub fn get_collision_modes(&self) -> &HashSet<CollisionMode> {
+        &self.collision_modes
+    }
+
+    pub fn set_collision_modes(&mut self, collision_modes: HashSet<CollisionMode>) {
+        self.collision_modes = collision_modes;
+    }
+
+    /// Takes the geometry, texture and shader of another object, keeping this object's name, transform and other state.
+    pub fn copy_render_data_from(&mut self, other: &Generic2DGraphicsObject) {
+        self.vertex_data = other.vertex_data.clone();
+        self.texture_coords = other.texture_coords.clone();
+        self.vao = Arc::clone(&other.vao);
+        self.position_vbo = Arc::clone(&other.position_vbo);
+        self.tex_vbo = Arc::clone(&other.tex_vbo);
+        self.shader_program = other.shader_program;
+        self.texture_name = other.texture_name.clone();
+        self.shader_name = other.shader_name.clone();
+        self.draw_mode = other.draw_mode;
+    }
+
+    pub fn set_texture_name(&mut self, texture_name: Option<String>) {
+        self.texture_name = texture_name;
+    }
+
+    pub fn get_texture_name(&self) -> Option<&str> {
+        self.texture_name.as_deref()
+    }
+
+    pub fn set_shader_name(&mut self, shader_name: Option<String>) {
+        self.shader_name = shader_name;
+    }
+
+    pub fn get_shader_name(&self) -> Option<&str> {
+        self.shader_name.as_deref()
+    }
+
+    pub fn get_shader_program(&self) -> GLuint {
+        self.shader_program
+    }
+
+    pub fn set_property(&mut self, key: &str, value: serde_json::Value) {
+        self.properties.insert(key.to_string(), value);
+    }
+
+    pub fn get_property(&self, key: &str) -> Option<&serde_json::Value> {
+        self.properties.get(key)
+    }
+
+    pub fn remove_property(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.properties.remove(key)
+    }
+
+    pub fn get_properties(&self) -> &HashMap<String, serde_json::Value> {
+        &self.properties
+    }
+
+    pub fn set_draw_mode(&mut self, draw_mode: DrawMode) {
+        self.draw_mode = draw_mode;
+    }
+
+    pub fn get_draw_mode(&self) -> DrawMode {
+        self.draw_mode
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    pub fn get_opacity(&self) -> f32 {
+        self.opacity
+    }
+
     pub fn print_debug(&self) {
         println!("Debug Info for Generic2DGraphicsObject:");
         println!("Name: {}", self.name);
@@ -278,3 +396,40 @@ pub enum CollisionMode {
     Circle,
     OBB,
 }
+
+impl CollisionMode {
+    /// Parses a collision mode as written in scene files.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "AABB" => Some(CollisionMode::AABB),
+            "Circle" => Some(CollisionMode::Circle),
+            "OBB" => Some(CollisionMode::OBB),
+            _ => None,
+        }
+    }
+}
+
+/// How an object's vertices are assembled into triangles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawMode {
+    TriangleFan, // One convex outline, the default for scene objects
+    Triangles, // Separate triangles, three vertices each, for meshes such as tilemaps
+}
+
+impl DrawMode {
+    /// Parses a draw mode as written in scene files.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "TriangleFan" => Some(DrawMode::TriangleFan),
+            "Triangles" => Some(DrawMode::Triangles),
+            _ => None,
+        }
+    }
+
+    fn gl_mode(&self) -> GLenum {
+        match self {
+            DrawMode::TriangleFan => gl::TRIANGLE_FAN,
+            DrawMode::Triangles => gl::TRIANGLES,
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::RwLock;
+use gl::types::GLuint;
+use serde::{Deserialize, Serialize};
+
+use super::internal_object::custom_shader::CustomShader;
+
+/// Where a shader's source comes from, as written in scene files.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ShaderDefinition {
+    Inline { vertex_shader: String, fragment_shader: String },
+    Files { vertex_path: String, fragment_path: String },
+}
+
+impl ShaderDefinition {
+    /// Returns the vertex and fragment source, reading them from disk for file definitions.
+    pub fn read_source(&self) -> Result<(String, String), String> {
+        match self {
+            ShaderDefinition::Inline { vertex_shader, fragment_shader } => Ok((vertex_shader.clone(), fragment_shader.clone())),
+            ShaderDefinition::Files { vertex_path, fragment_path } => {
+                let vertex_shader = fs::read_to_string(vertex_path).map_err(|e| format!("Failed to read vertex shader '{}': {}", vertex_path, e))?;
+                let fragment_shader = fs::read_to_string(fragment_path).map_err(|e| format!("Failed to read fragment shader '{}': {}", fragment_path, e))?;
+                Ok((vertex_shader, fragment_shader))
+            },
+        }
+    }
+}
+
+/// Shared library of named shaders. Programs are cached by their source,
+/// so identical shaders are compiled once no matter how many names or objects use them.
+pub struct ShaderManager {
+    definitions: RwLock<HashMap<String, ShaderDefinition>>,
+    owners: RwLock<HashMap<String, String>>, // File that defined each shader, library shaders have none
+    programs: RwLock<HashMap<(String, String), GLuint>>,
+    definition_programs: RwLock<HashMap<ShaderDefinition, GLuint>>, // So shader files are read once, not once per object
+}
+
+impl Default for ShaderManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShaderManager {
+    pub fn new() -> Self {
+        ShaderManager {
+            definitions: RwLock::new(HashMap::new()),
+            owners: RwLock::new(HashMap::new()),
+            programs: RwLock::new(HashMap::new()),
+            definition_programs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Adds a shader under a name, replacing any earlier definition of that name.
+    /// Objects already using the old definition keep their program.
+    pub fn register_shader(&self, name: &str, definition: ShaderDefinition) {
+        self.definitions.write().unwrap().insert(name.to_string(), definition);
+        self.owners.write().unwrap().remove(name);
+    }
+
+    /// Adds a set of shaders defined by a file, such as a scene's shaders section, all or none.
+    /// See check_definition for which names may be registered again.
+    pub fn register_shaders(&self, owner: &str, shaders: &BTreeMap<String, ShaderDefinition>) -> Result<(), String> {
+        let mut definitions = self.definitions.write().unwrap();
+        let mut owners = self.owners.write().unwrap();
+        for (name, definition) in shaders {
+            Self::check_against(&definitions, &owners, owner, name, definition)?;
+        }
+        for (name, definition) in shaders {
+            if definitions.insert(name.clone(), definition.clone()).is_none() {
+                owners.insert(name.clone(), owner.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks whether a file may define a shader under a name. A name already in the library may be defined
+    /// again with the same definition, and changed only by the file that first defined it, such as when a
+    /// scene is reloaded. Other files cannot change a shader objects outside them use.
+    pub fn check_definition(&self, owner: &str, name: &str, definition: &ShaderDefinition) -> Result<(), String> {
+        Self::check_against(&self.definitions.read().unwrap(), &self.owners.read().unwrap(), owner, name, definition)
+    }
+
+    fn check_against(definitions: &HashMap<String, ShaderDefinition>, owners: &HashMap<String, String>, owner: &str, name: &str, definition: &ShaderDefinition) -> Result<(), String> {
+        let redefined = definitions.get(name).is_some_and(|existing| existing != definition);
+        if redefined && owners.get(name).map(String::as_str) != Some(owner) {
+            return Err(format!("Shader '{}' is already in the shader library with a different definition", name));
+        }
+        Ok(())
+    }
+
+    pub fn has_shader(&self, name: &str) -> bool {
+        self.definitions.read().unwrap().contains_key(name)
+    }
+
+    pub fn get_shader_definition(&self, name: &str) -> Option<ShaderDefinition> {
+        self.definitions.read().unwrap().get(name).cloned()
+    }
+
+    /// Returns the program for a named shader, compiling it the first time its source is seen.
+    pub fn get_program(&self, name: &str) -> Result<GLuint, String> {
+        let definition = self.get_shader_definition(name).ok_or_else(|| format!("Shader '{}' is not defined", name))?;
+        self.get_program_for_definition(&definition)
+    }
+
+    /// Returns the program for a definition whether or not it is in the library.
+    /// File shaders are read the first time the definition is seen, later edits to the files are not picked up.
+    pub fn get_program_for_definition(&self, definition: &ShaderDefinition) -> Result<GLuint, String> {
+        if let Some(&program) = self.definition_programs.read().unwrap().get(definition) {
+            return Ok(program);
+        }
+
+        let (vertex_shader, fragment_shader) = definition.read_source()?;
+        let program = self.get_program_for_source(&vertex_shader, &fragment_shader);
+        self.definition_programs.write().unwrap().insert(definition.clone(), program);
+        Ok(program)
+    }
+
+    /// Returns the program for the given source, compiling it only if no identical program exists.
+    pub fn get_program_for_source(&self, vertex_shader: &str, fragment_shader: &str) -> GLuint {
+        let key = (vertex_shader.to_string(), fragment_shader.to_string());
+        if let Some(&program) = self.programs.read().unwrap().get(&key) {
+            return program;
+        }
+
+        let program = CustomShader::new(vertex_shader, fragment_shader).get_shader_program();
+        self.programs.write().unwrap().insert(key, program);
+        program
+    }
+
+    // Register every <name>.vert and <name>.frag pair in a directory as a file shader named <name>
+    pub fn load_shaders_from_directory(&self, dir_path: &str) -> Result<(), String> {
+        let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+
+        for path in paths {
+            let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
+            let vertex_path = entry.path();
+
+            if vertex_path.is_file() && vertex_path.extension().is_some_and(|extension| extension == "vert") {
+                let fragment_path = vertex_path.with_extension("frag");
+                if !fragment_path.is_file() {
+                    return Err(format!("Vertex shader '{}' has no matching .frag file", vertex_path.display()));
+                }
+
+                let name = vertex_path.file_stem().and_then(|name| name.to_str()).ok_or("Invalid file name")?;
+                self.register_shader(name, ShaderDefinition::Files {
+                    vertex_path: vertex_path.to_str().ok_or("Invalid file name")?.to_string(),
+                    fragment_path: fragment_path.to_str().ok_or("Invalid file name")?.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use std::{ffi::c_void, sync::{atomic::{AtomicU32, Ordering}, Once}};
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+
+// Stand-in OpenGL entry points so tests can build objects and shader programs without a context
+
+static LOAD: Once = Once::new();
+static NEXT_NAME: AtomicU32 = AtomicU32::new(1);
+
+// Every call not listed below does nothing and returns 0
+extern "system" fn do_nothing() -> usize {
+    0
+}
+
+// Compile and link status queries report success
+extern "system" fn report_success(_object: GLuint, _parameter: GLenum, value: *mut GLint) {
+    unsafe { *value = 1 };
+}
+
+// glCreateShader and glCreateProgram hand out a new name each call
+extern "system" fn create_name() -> GLuint {
+    NEXT_NAME.fetch_add(1, Ordering::Relaxed)
+}
+
+// glGenBuffers and glGenVertexArrays fill the array with new names
+extern "system" fn generate_names(count: GLsizei, names: *mut GLuint) {
+    for index in 0..count as usize {
+        unsafe { *names.add(index) = create_name() };
+    }
+}
+
+/// Loads the stand-in GL functions. Safe to call from every test.
+pub fn load() {
+    LOAD.call_once(|| {
+        gl::load_with(|name| match name {
+            "glGetShaderiv" | "glGetProgramiv" => report_success as *const c_void,
+            "glCreateShader" | "glCreateProgram" => create_name as *const c_void,
+            "glGenBuffers" | "glGenVertexArrays" => generate_names as *const c_void,
+            _ => do_nothing as *const c_void,
+        });
+    });
+}
@@ -1,13 +1,29 @@
 use std::collections::HashMap;
 use std::fs;
+use std::path::PathBuf;
 use std::sync::RwLock;
 use gl::types::{GLint, GLsizei, GLuint};
+use glfw::PixelImage;
 use image::{self, GenericImageView}; // Ensure you have this crate in your Cargo.toml
 
+/// An image decoded to RGBA bytes, ready to be uploaded. Decoding needs no GL context,
+/// so it can be done off the main thread.
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
 pub struct TextureManager {
     textures: RwLock<HashMap<String, GLuint>>,
 }
 
+impl Default for TextureManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TextureManager {
     pub fn new() -> Self {
         TextureManager {
@@ -33,11 +49,32 @@ impl TextureManager {
         }
     }
 
+    /// Uploads an already decoded image as a texture. Must be called on the thread that owns the GL context.
+    /// Returns the existing texture if one is already loaded under the name.
+    pub fn upload_texture(&self, name: &str, image: &DecodedImage) -> GLuint {
+        let mut textures = self.textures.write().unwrap();
+        if let Some(&texture_id) = textures.get(name) {
+            return texture_id;
+        }
+
+        let texture_id = Self::create_texture(image);
+        textures.insert(name.to_string(), texture_id);
+        texture_id
+    }
+
     fn load_texture_from_file(path: &str) -> Result<GLuint, String> {
-        let img = image::open(path).map_err(|_| "Failed to load texture".to_string())?;
-        let data = img.to_rgba8();
+        let image = Self::decode_image(path)?;
+        Ok(Self::create_texture(&image))
+    }
+
+    /// Reads and decodes an image file without touching GL.
+    pub fn decode_image(path: &str) -> Result<DecodedImage, String> {
+        let img = Self::open_image(path)?;
         let (width, height) = img.dimensions();
-    
+        Ok(DecodedImage { width, height, pixels: img.to_rgba8().into_raw() })
+    }
+
+    fn create_texture(image: &DecodedImage) -> GLuint {
         let mut texture: GLuint = 0;
         unsafe {
             gl::GenTextures(1, &mut texture);  // Generate texture ID
@@ -48,12 +85,12 @@ impl TextureManager {
                 gl::TEXTURE_2D,
                 0,
                 gl::RGBA as GLint,
-                width as GLsizei,
-                height as GLsizei,
+                image.width as GLsizei,
+                image.height as GLsizei,
                 0,
                 gl::RGBA,
                 gl::UNSIGNED_BYTE,
-                data.as_ptr() as *const _,
+                image.pixels.as_ptr() as *const _,
             );
 
             // Set texture parameters
@@ -66,7 +103,29 @@ impl TextureManager {
             gl::BindTexture(gl::TEXTURE_2D, 0);  // Unbind the texture
         }
 
-        Ok(texture) // Return the texture ID
+        texture // Return the texture ID
+    }
+
+    fn open_image(path: &str) -> Result<image::DynamicImage, String> {
+        image::open(path).map_err(|_| "Failed to load texture".to_string())
+    }
+
+    /// Loads an image file into the packed RGBA pixel format GLFW expects for icons and cursors.
+    pub fn load_pixel_image(path: &str) -> Result<PixelImage, String> {
+        let img = Self::open_image(path)?;
+        let (width, height) = img.dimensions();
+        let pixels = img.to_rgba8()
+            .chunks_exact(4)
+            .map(|rgba| u32::from_ne_bytes([rgba[0], rgba[1], rgba[2], rgba[3]]))
+            .collect();
+
+        Ok(PixelImage { width, height, pixels })
+    }
+
+    // Lets tests stand in for loaded textures without a GL context
+    #[cfg(test)]
+    pub fn insert_texture_id(&self, name: &str, texture_id: GLuint) {
+        self.textures.write().unwrap().insert(name.to_string(), texture_id);
     }
 
     pub fn get_texture_id(&self, name: &str) -> Option<GLuint> {
@@ -76,7 +135,17 @@ impl TextureManager {
 
     // New method to load all textures from a specified directory
     pub fn load_textures_from_directory(&self, dir_path: &str) -> Result<(), String> {
+        for (name, path) in Self::list_textures_in_directory(dir_path)? {
+            self.load_texture(&name, path.to_str().unwrap()).map_err(|e| format!("Error loading texture '{}': {}", name, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the image files in a directory with the texture names they load under, the file name without its extension.
+    pub fn list_textures_in_directory(dir_path: &str) -> Result<Vec<(String, PathBuf)>, String> {
         let paths = fs::read_dir(dir_path).map_err(|_| "Failed to read directory".to_string())?;
+        let mut textures = Vec::new();
 
         for path in paths {
             let entry = path.map_err(|_| "Failed to read directory entry".to_string())?;
@@ -89,12 +158,12 @@ impl TextureManager {
                     if extension == "png" || extension == "jpg" || extension == "jpeg" {
                         // Load the texture with the file name (without extension)
                         let name = file_name.trim_end_matches(".png").trim_end_matches(".jpg").trim_end_matches(".jpeg");
-                        self.load_texture(name, full_path.to_str().unwrap()).map_err(|e| format!("Error loading texture '{}': {}", name, e))?;
+                        textures.push((name.to_string(), full_path));
                     }
                 }
             }
         }
 
-        Ok(())
+        Ok(textures)
     }
 }
@@ -1,2 +1,3 @@
+pub mod frame_limiter;
 pub mod master_clock;
 pub mod master_graphics_list;
\ No newline at end of file
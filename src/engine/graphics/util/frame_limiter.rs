@@ -0,0 +1,94 @@
+use std::{thread, time::{Duration, Instant}};
+
+use glfw::{Glfw, SwapInterval};
+
+/// Controls vsync and optionally caps the frame rate in software.
+pub struct FrameLimiter {
+    swap_interval: u32, // 0 disables vsync, N waits for N vertical blanks per swap
+    target_frame_time: Option<Duration>, // None leaves the frame rate uncapped
+    spin_threshold: Duration, // How close to the deadline sleeping stops and spinning starts
+    next_frame_deadline: Instant,
+}
+
+impl FrameLimiter {
+    /// Creates a new FrameLimiter. A max_fps of None or zero disables the software cap.
+    pub fn new(swap_interval: u32, max_fps: Option<f32>) -> Self {
+        Self {
+            swap_interval,
+            target_frame_time: Self::frame_time_for(max_fps),
+            spin_threshold: Duration::from_millis(2),
+            next_frame_deadline: Instant::now(),
+        }
+    }
+
+    fn frame_time_for(max_fps: Option<f32>) -> Option<Duration> {
+        max_fps.filter(|fps| *fps > 0.0).map(|fps| Duration::from_secs_f32(1.0 / fps))
+    }
+
+    /// Applies the swap interval to the current OpenGL context.
+    pub fn apply_swap_interval(&self, glfw: &mut Glfw) {
+        let interval = match self.swap_interval {
+            0 => SwapInterval::None,
+            interval => SwapInterval::Sync(interval),
+        };
+        glfw.set_swap_interval(interval);
+    }
+
+    /// Changes the swap interval and applies it to the current OpenGL context.
+    pub fn set_swap_interval(&mut self, glfw: &mut Glfw, swap_interval: u32) {
+        self.swap_interval = swap_interval;
+        self.apply_swap_interval(glfw);
+    }
+
+    /// Enables or disables vsync, syncing every frame when enabled.
+    pub fn set_vsync(&mut self, glfw: &mut Glfw, enabled: bool) {
+        self.set_swap_interval(glfw, if enabled { 1 } else { 0 });
+    }
+
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.target_frame_time = Self::frame_time_for(max_fps);
+        self.next_frame_deadline = Instant::now();
+    }
+
+    /// Sets how long before the deadline the limiter stops sleeping and spins.
+    /// Larger values are more accurate on systems with coarse sleep granularity but use more CPU.
+    pub fn set_spin_threshold(&mut self, spin_threshold: Duration) {
+        self.spin_threshold = spin_threshold;
+    }
+
+    pub fn get_swap_interval(&self) -> u32 {
+        self.swap_interval
+    }
+
+    pub fn get_max_fps(&self) -> Option<f32> {
+        self.target_frame_time.map(|frame_time| 1.0 / frame_time.as_secs_f32())
+    }
+
+    /// Blocks until the next frame is due. Call once per frame after swapping buffers.
+    pub fn wait_for_next_frame(&mut self) {
+        let Some(target_frame_time) = self.target_frame_time else {
+            return;
+        };
+
+        // Advance from the previous deadline rather than from now so frame times do not drift
+        self.next_frame_deadline += target_frame_time;
+
+        let now = Instant::now();
+        if self.next_frame_deadline <= now {
+            // Running behind, start the schedule again instead of rushing to catch up
+            self.next_frame_deadline = now;
+            return;
+        }
+
+        // Sleep for most of the remaining time, the OS scheduler is not precise enough for the rest
+        let remaining = self.next_frame_deadline - now;
+        if remaining > self.spin_threshold {
+            thread::sleep(remaining - self.spin_threshold);
+        }
+
+        // Spin for the final stretch
+        while Instant::now() < self.next_frame_deadline {
+            std::hint::spin_loop();
+        }
+    }
+}
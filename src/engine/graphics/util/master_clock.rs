@@ -3,6 +3,14 @@ use std::time::Instant;
 pub struct MasterClock {
     last_time: Instant,
     delta_time: f32,
+    frame_count: u64, // Number of updates so far
+    elapsed_time: f64, // Sum of every delta time, so it follows replayed timing too
+}
+
+impl Default for MasterClock {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MasterClock {
@@ -11,6 +19,8 @@ impl MasterClock {
         Self {
             last_time: Instant::now(),
             delta_time: 0.0,
+            frame_count: 0,
+            elapsed_time: 0.0,
         }
     }
 
@@ -19,10 +29,28 @@ impl MasterClock {
         let current_time = Instant::now();
         self.delta_time = current_time.duration_since(self.last_time).as_secs_f32();
         self.last_time = current_time;
+        self.frame_count += 1;
+        self.elapsed_time += self.delta_time as f64;
+    }
+
+    /// Overrides the delta time for the current frame, used to replay recorded sessions.
+    pub fn set_delta_time(&mut self, delta_time: f32) {
+        self.elapsed_time += (delta_time - self.delta_time) as f64;
+        self.delta_time = delta_time;
     }
 
     /// Returns the time elapsed since the last update.
     pub fn get_delta_time(&self) -> f32 {
         self.delta_time
     }
+
+    /// Returns the seconds elapsed since the clock was created.
+    pub fn get_elapsed_time(&self) -> f64 {
+        self.elapsed_time
+    }
+
+    /// Returns the number of the current frame, starting from 1 after the first update.
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
 }
@@ -3,8 +3,28 @@ use nalgebra::Matrix4;
 
 use crate::engine::{graphics::internal_object::graphics_object::Generic2DGraphicsObject, scenes::scene::Scene};
 
+/// What to do when an object being loaded has the same name as a live object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameCollision {
+    Replace, // The new object replaces the live one
+    Namespace, // The new object is renamed to scene_name.object_name
+}
+
 pub struct MasterGraphicsList {
     objects: Arc<RwLock<HashMap<String, Arc<RwLock<Generic2DGraphicsObject>>>>>, // Change key type to String
+    object_scenes: Arc<RwLock<HashMap<String, ObjectSource>>>, // Object name to where it was loaded from
+}
+
+// The scene an object was loaded from and its name in that scene, which differs from its live name if it was namespaced
+struct ObjectSource {
+    scene: String,
+    name: String,
+}
+
+impl Default for MasterGraphicsList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MasterGraphicsList {
@@ -12,6 +32,7 @@ impl MasterGraphicsList {
     pub fn new() -> Self {
         MasterGraphicsList {
             objects: Arc::new(RwLock::new(HashMap::new())),
+            object_scenes: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -21,6 +42,7 @@ impl MasterGraphicsList {
         let name = binding.get_name();
         let mut objects = self.objects.write().unwrap();
         objects.insert(name.to_owned(), obj.clone());
+        self.object_scenes.write().unwrap().remove(name); // A replaced object no longer belongs to its scene
     }
     
 
@@ -33,6 +55,68 @@ impl MasterGraphicsList {
         }
     }
 
+    // Add a scene's objects alongside those already loaded, remembering which scene they came from.
+    // Returns the names the objects were added under.
+    pub fn load_scene_additive(&self, scene_name: &str, scene: &Scene, collision: NameCollision) -> Vec<String> {
+        scene.get_objects().iter()
+            .map(|obj| self.add_scene_object(scene_name, obj.read().unwrap().clone(), collision))
+            .collect()
+    }
+
+    // Add one object from a scene, renaming it if it clashes and namespacing is on. Returns the name it was added under.
+    pub fn add_scene_object(&self, scene_name: &str, mut obj: Generic2DGraphicsObject, collision: NameCollision) -> String {
+        let source = ObjectSource { scene: scene_name.to_string(), name: obj.get_name().to_owned() };
+
+        if collision == NameCollision::Namespace && self.get_object(obj.get_name()).is_some() {
+            let namespaced = format!("{}.{}", scene_name, obj.get_name());
+            let name = if self.get_object(&namespaced).is_none() {
+                namespaced
+            } else {
+                (1..).map(|index| format!("{}_{}", namespaced, index)).find(|name| self.get_object(name).is_none()).unwrap()
+            };
+            obj.set_name(name);
+        }
+
+        let name = obj.get_name().to_owned();
+        self.add_object(Arc::new(RwLock::new(obj)));
+        self.object_scenes.write().unwrap().insert(name.clone(), source);
+        name
+    }
+
+    // Get the name of the scene an object was loaded from, None for objects added directly
+    pub fn get_object_scene(&self, name: &str) -> Option<String> {
+        self.object_scenes.read().unwrap().get(name).map(|source| source.scene.clone())
+    }
+
+    // Get the live names of the objects loaded from a scene under a name in that scene
+    pub fn find_scene_object(&self, scene_name: &str, source_name: &str) -> Vec<String> {
+        let mut names: Vec<String> = self.object_scenes.read().unwrap().iter()
+            .filter(|(_, source)| source.scene == scene_name && source.name == source_name)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    // Get the names of the live objects that were loaded from a scene
+    pub fn get_scene_objects(&self, scene_name: &str) -> Vec<String> {
+        let mut names: Vec<String> = self.object_scenes.read().unwrap().iter()
+            .filter(|(_, source)| source.scene == scene_name)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    // Remove only the objects loaded from a scene, returning their names
+    pub fn remove_scene_objects(&self, scene_name: &str) -> Vec<String> {
+        let names = self.get_scene_objects(scene_name);
+        for name in &names {
+            self.remove_object(name);
+        }
+        names
+    }
+
     // Get an object by name
     pub fn get_object(&self, name: &str) -> Option<Arc<RwLock<Generic2DGraphicsObject>>> {
         let objects = self.objects.read().unwrap();
@@ -49,6 +133,9 @@ impl MasterGraphicsList {
         let objects = self.objects.read().unwrap(); // Lock for reading the list
         for obj in objects.values() {
             if let Ok(mut obj) = obj.write() { // Lock each object for writing (to update model matrix)
+                if !obj.is_visible() {
+                    continue;
+                }
                 obj.update_model_matrix(); // Update the model matrix first
                 obj.apply_transform(projection_matrix); // Apply the projection matrix
                 obj.draw(); // Now draw the object
@@ -63,11 +150,13 @@ impl MasterGraphicsList {
     pub fn remove_object(&self, name: &str) {
         let mut objects = self.objects.write().unwrap();
         objects.remove(name);
+        self.object_scenes.write().unwrap().remove(name);
     }
 
     // Remove all objects from the list
     pub fn remove_all(&self) {
         let mut objects = self.objects.write().unwrap();
         objects.clear();
+        self.object_scenes.write().unwrap().clear();
     }
 }
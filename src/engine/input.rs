@@ -0,0 +1,6 @@
+pub mod combo;
+pub mod gamepad;
+pub mod input_binding;
+pub mod input_map;
+pub mod input_recording;
+pub mod text_input;
\ No newline at end of file
@@ -0,0 +1,240 @@
+use std::{collections::{HashMap, HashSet}, fs::File, io::{self, Read}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::key_states::State;
+
+use super::{input_binding::InputBinding, input_map::{InputMap, AXIS_PRESS_THRESHOLD}};
+
+/// A pattern of inputs that triggers a named combo.
+/// Declared in JSON with a "type" of "Chord", "Sequence" or "DoubleTap", with times in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Combo {
+    /// Every input held together, triggering on the frame the last one goes down, such as Ctrl+S.
+    Chord { inputs: Vec<InputBinding> },
+    /// Chords pressed in order, each within the window of the one before, such as down, down-forward, forward + punch.
+    Sequence { steps: Vec<Vec<InputBinding>>, window: f32 },
+    /// The same input pressed twice within the window.
+    DoubleTap { input: InputBinding, window: f32 },
+}
+
+// How far a sequence has got
+#[derive(Debug, Clone, Copy, Default)]
+struct SequenceProgress {
+    next_step: usize,
+    last_step_time: f64,
+}
+
+/// Recognises combos from the input state each frame.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComboRecognizer {
+    #[serde(default)]
+    combos: HashMap<String, Combo>,
+    #[serde(skip)]
+    sequences: HashMap<String, SequenceProgress>,
+    #[serde(skip)]
+    last_taps: HashMap<String, f64>, // Time of the last unmatched tap for each double tap combo
+    #[serde(skip)]
+    triggered: HashSet<String>, // Combos completed this frame
+}
+
+impl ComboRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load_from_json(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(file_path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        let recognizer: ComboRecognizer = serde_json::from_str(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(recognizer)
+    }
+
+    /// Adds a combo, replacing any combo with the same name.
+    pub fn add_combo(&mut self, name: &str, combo: Combo) {
+        self.remove_combo(name);
+        self.combos.insert(name.to_string(), combo);
+    }
+
+    pub fn remove_combo(&mut self, name: &str) {
+        self.combos.remove(name);
+        self.sequences.remove(name);
+        self.last_taps.remove(name);
+        self.triggered.remove(name);
+    }
+
+    pub fn get_combo(&self, name: &str) -> Option<&Combo> {
+        self.combos.get(name)
+    }
+
+    /// True during the frame the combo was completed.
+    pub fn is_triggered(&self, name: &str) -> bool {
+        self.triggered.contains(name)
+    }
+
+    /// Returns every combo completed this frame, sorted by name.
+    pub fn get_triggered(&self) -> Vec<String> {
+        let mut triggered: Vec<String> = self.triggered.iter().cloned().collect();
+        triggered.sort();
+        triggered
+    }
+
+    // All inputs down with at least one of them pressed this frame. An input pressed and released
+    // within the frame counts as down, so quick taps at a low frame rate are not lost
+    fn chord_pressed(inputs: &[InputBinding], state: &State) -> bool {
+        !inputs.is_empty()
+            && inputs.iter().all(|input| InputMap::binding_value(input, state) >= AXIS_PRESS_THRESHOLD || InputMap::binding_just_pressed(input, state))
+            && inputs.iter().any(|input| InputMap::binding_just_pressed(input, state))
+    }
+
+    /// Checks every combo against this frame's input. Call once per frame after input is collected,
+    /// with the engine clock's elapsed time.
+    pub fn update(&mut self, state: &State, time: f64) {
+        self.triggered.clear();
+
+        for (name, combo) in &self.combos {
+            let completed = match combo {
+                Combo::Chord { inputs } => Self::chord_pressed(inputs, state),
+                Combo::Sequence { steps, window } => {
+                    let progress = self.sequences.entry(name.clone()).or_default();
+
+                    // Too slow, start over
+                    if progress.next_step > 0 && time - progress.last_step_time > *window as f64 {
+                        progress.next_step = 0;
+                    }
+
+                    if steps.get(progress.next_step).is_some_and(|step| Self::chord_pressed(step, state)) {
+                        progress.next_step += 1;
+                        progress.last_step_time = time;
+                    } else if progress.next_step > 0 && steps.first().is_some_and(|step| Self::chord_pressed(step, state)) {
+                        // A fresh first step restarts the sequence rather than breaking it
+                        progress.next_step = 1;
+                        progress.last_step_time = time;
+                    }
+
+                    if progress.next_step > 0 && progress.next_step == steps.len() {
+                        progress.next_step = 0;
+                        true
+                    } else {
+                        false
+                    }
+                },
+                Combo::DoubleTap { input, window } => {
+                    if InputMap::binding_just_pressed(input, state) {
+                        match self.last_taps.remove(name) {
+                            Some(last_tap) if time - last_tap <= *window as f64 => true,
+                            _ => {
+                                self.last_taps.insert(name.clone(), time);
+                                false
+                            },
+                        }
+                    } else {
+                        false
+                    }
+                },
+            };
+
+            if completed {
+                self.triggered.insert(name.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::{Action, Key, Modifiers, WindowEvent};
+
+    use super::*;
+
+    // Feeds key events to State one frame at a time, as the event loop does
+    struct Frames {
+        state: State,
+        combos: ComboRecognizer,
+    }
+
+    impl Frames {
+        fn new(name: &str, combo: Combo) -> Self {
+            let mut combos = ComboRecognizer::new();
+            combos.add_combo(name, combo);
+            Self { state: State::new(), combos }
+        }
+
+        // Runs one frame at the given time and returns the combos it triggered
+        fn frame(&mut self, time: f64, events: &[(Key, Action)]) -> Vec<String> {
+            self.state.begin_frame(0.0);
+            for &(key, action) in events {
+                self.state.handle_event(WindowEvent::Key(key, 0, action, Modifiers::empty()));
+            }
+            self.combos.update(&self.state, time);
+            self.combos.get_triggered()
+        }
+
+        // Presses and releases a key within one frame
+        fn tap(&mut self, time: f64, key: Key) -> Vec<String> {
+            self.frame(time, &[(key, Action::Press), (key, Action::Release)])
+        }
+    }
+
+    fn keys(keys: &[Key]) -> Vec<InputBinding> {
+        keys.iter().map(|&key| InputBinding::Key(key)).collect()
+    }
+
+    #[test]
+    fn chords_fire_only_on_the_frame_their_last_input_goes_down() {
+        let mut frames = Frames::new("save", Combo::Chord { inputs: keys(&[Key::LeftControl, Key::S]) });
+
+        assert!(frames.frame(0.0, &[(Key::LeftControl, Action::Press)]).is_empty());
+        assert_eq!(frames.frame(0.1, &[(Key::S, Action::Press)]), vec!["save"]);
+        assert!(frames.frame(0.2, &[]).is_empty());
+        assert!(frames.frame(0.3, &[(Key::S, Action::Repeat)]).is_empty());
+
+        assert!(frames.frame(0.4, &[(Key::S, Action::Release)]).is_empty());
+        assert_eq!(frames.frame(0.5, &[(Key::S, Action::Press)]), vec!["save"]);
+    }
+
+    #[test]
+    fn sequences_reset_when_a_step_comes_too_late() {
+        let mut frames = Frames::new("dash", Combo::Sequence { steps: vec![keys(&[Key::Down]), keys(&[Key::Right])], window: 0.3 });
+
+        assert!(frames.tap(0.0, Key::Down).is_empty());
+        assert!(frames.tap(0.5, Key::Right).is_empty());
+
+        assert!(frames.tap(1.0, Key::Down).is_empty());
+        assert_eq!(frames.tap(1.2, Key::Right), vec!["dash"]);
+        assert!(frames.tap(1.4, Key::Right).is_empty());
+    }
+
+    #[test]
+    fn a_new_first_step_restarts_a_sequence() {
+        let mut frames = Frames::new("abc", Combo::Sequence { steps: vec![keys(&[Key::A]), keys(&[Key::B]), keys(&[Key::C])], window: 0.3 });
+
+        assert!(frames.tap(0.0, Key::A).is_empty());
+        assert!(frames.tap(0.1, Key::B).is_empty());
+        assert!(frames.tap(0.2, Key::A).is_empty());
+        assert!(frames.tap(0.3, Key::C).is_empty());
+
+        assert!(frames.tap(0.4, Key::A).is_empty());
+        assert!(frames.tap(0.5, Key::B).is_empty());
+        assert_eq!(frames.tap(0.6, Key::C), vec!["abc"]);
+    }
+
+    #[test]
+    fn double_taps_must_land_within_the_window() {
+        let mut frames = Frames::new("dodge", Combo::DoubleTap { input: InputBinding::Key(Key::Space), window: 0.25 });
+
+        assert!(frames.tap(0.0, Key::Space).is_empty());
+        assert_eq!(frames.tap(0.2, Key::Space), vec!["dodge"]);
+        // The tap that completed a double tap does not start another
+        assert!(frames.tap(0.3, Key::Space).is_empty());
+
+        // Too slow, so the late tap becomes the first of a new double tap
+        assert!(frames.tap(1.0, Key::Space).is_empty());
+        assert_eq!(frames.tap(1.25, Key::Space), vec!["dodge"]);
+    }
+}
@@ -0,0 +1,343 @@
+use glfw::{Action, GamepadAxis, GamepadButton, Glfw, JoystickId};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::key_states::State;
+
+pub const MAX_GAMEPADS: usize = 16; // GLFW supports joystick ids 0 through 15
+pub const GAMEPAD_BUTTON_COUNT: usize = 15;
+pub const GAMEPAD_AXIS_COUNT: usize = 6;
+
+/// The buttons and axes of one gamepad at a point in time, indexed by the GLFW gamepad enums.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct GamepadSnapshot {
+    pub buttons: [bool; GAMEPAD_BUTTON_COUNT],
+    pub axes: [f32; GAMEPAD_AXIS_COUNT], // Sticks range from -1.0 to 1.0, triggers from -1.0 (released) to 1.0
+}
+
+impl GamepadSnapshot {
+    pub fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    pub fn get_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+}
+
+/// Source of joystick data. The GLFW implementation is used at runtime,
+/// other implementations can stand in for real hardware.
+pub trait JoystickProvider {
+    fn is_present(&self, index: usize) -> bool;
+    /// True if the joystick has a standard gamepad mapping.
+    fn is_gamepad(&self, index: usize) -> bool;
+    fn get_name(&self, index: usize) -> Option<String>;
+    fn get_gamepad_snapshot(&self, index: usize) -> Option<GamepadSnapshot>;
+}
+
+/// Reads joysticks through GLFW's gamepad API.
+pub struct GlfwJoystickProvider {
+    glfw: Glfw,
+}
+
+impl GlfwJoystickProvider {
+    pub fn new(glfw: Glfw) -> Self {
+        Self { glfw }
+    }
+}
+
+impl JoystickProvider for GlfwJoystickProvider {
+    fn is_present(&self, index: usize) -> bool {
+        JoystickId::from_i32(index as i32).is_some_and(|id| self.glfw.get_joystick(id).is_present())
+    }
+
+    fn is_gamepad(&self, index: usize) -> bool {
+        JoystickId::from_i32(index as i32).is_some_and(|id| self.glfw.get_joystick(id).is_gamepad())
+    }
+
+    fn get_name(&self, index: usize) -> Option<String> {
+        let joystick = self.glfw.get_joystick(JoystickId::from_i32(index as i32)?);
+        joystick.get_gamepad_name().or_else(|| joystick.get_name())
+    }
+
+    fn get_gamepad_snapshot(&self, index: usize) -> Option<GamepadSnapshot> {
+        let gamepad_state = self.glfw.get_joystick(JoystickId::from_i32(index as i32)?).get_gamepad_state()?;
+
+        let mut snapshot = GamepadSnapshot::default();
+        for (i, pressed) in snapshot.buttons.iter_mut().enumerate() {
+            if let Some(button) = GamepadButton::from_i32(i as i32) {
+                *pressed = gamepad_state.get_button_state(button) == Action::Press;
+            }
+        }
+        for (i, value) in snapshot.axes.iter_mut().enumerate() {
+            if let Some(axis) = GamepadAxis::from_i32(i as i32) {
+                *value = gamepad_state.get_axis(axis);
+            }
+        }
+        Some(snapshot)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected { index: usize, name: String },
+    Disconnected { index: usize },
+}
+
+/// Polls connected gamepads, applies dead zones and feeds the results into State.
+pub struct GamepadManager {
+    provider: Box<dyn JoystickProvider>,
+    connected: [bool; MAX_GAMEPADS],
+    stick_dead_zone: f32,
+    trigger_dead_zone: f32,
+}
+
+impl GamepadManager {
+    pub fn new(provider: Box<dyn JoystickProvider>) -> Self {
+        Self {
+            provider,
+            connected: [false; MAX_GAMEPADS],
+            stick_dead_zone: 0.15,
+            trigger_dead_zone: 0.05,
+        }
+    }
+
+    /// Sets the fraction of stick travel, from 0.0 to 1.0, that is ignored around the center.
+    pub fn set_stick_dead_zone(&mut self, dead_zone: f32) {
+        self.stick_dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+
+    /// Sets the fraction of trigger travel, from 0.0 to 1.0, that is ignored at rest.
+    pub fn set_trigger_dead_zone(&mut self, dead_zone: f32) {
+        self.trigger_dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+
+    pub fn get_connected_gamepads(&self) -> Vec<usize> {
+        (0..MAX_GAMEPADS).filter(|&index| self.connected[index]).collect()
+    }
+
+    pub fn get_name(&self, index: usize) -> Option<String> {
+        self.provider.get_name(index)
+    }
+
+    /// Polls every joystick slot, updating State and reporting connects and disconnects.
+    /// Joysticks without a gamepad mapping are ignored.
+    pub fn update(&mut self, state: &mut State) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+
+        for index in 0..MAX_GAMEPADS {
+            let snapshot = if self.provider.is_present(index) && self.provider.is_gamepad(index) {
+                self.provider.get_gamepad_snapshot(index)
+            } else {
+                None
+            };
+
+            match snapshot {
+                Some(snapshot) => {
+                    if !self.connected[index] {
+                        self.connected[index] = true;
+                        let name = self.provider.get_name(index).unwrap_or_else(|| format!("Gamepad {}", index));
+                        events.push(GamepadEvent::Connected { index, name });
+                    }
+                    state.set_gamepad_snapshot(index, self.apply_dead_zones(snapshot));
+                },
+                None => {
+                    if self.connected[index] {
+                        self.connected[index] = false;
+                        state.remove_gamepad(index);
+                        events.push(GamepadEvent::Disconnected { index });
+                    }
+                },
+            }
+        }
+
+        events
+    }
+
+    fn apply_dead_zones(&self, mut snapshot: GamepadSnapshot) -> GamepadSnapshot {
+        // Sticks use a radial dead zone so diagonals are not clipped
+        for (x_axis, y_axis) in [(GamepadAxis::AxisLeftX, GamepadAxis::AxisLeftY), (GamepadAxis::AxisRightX, GamepadAxis::AxisRightY)] {
+            let x = snapshot.axes[x_axis as usize];
+            let y = snapshot.axes[y_axis as usize];
+            let magnitude = (x * x + y * y).sqrt();
+
+            let (x, y) = if magnitude <= self.stick_dead_zone {
+                (0.0, 0.0)
+            } else {
+                // Rescale so output starts at zero just outside the dead zone
+                let scaled = ((magnitude - self.stick_dead_zone) / (1.0 - self.stick_dead_zone)).min(1.0);
+                (x / magnitude * scaled, y / magnitude * scaled)
+            };
+            snapshot.axes[x_axis as usize] = x;
+            snapshot.axes[y_axis as usize] = y;
+        }
+
+        // Triggers rest at -1.0, remap them to 0.0 to 1.0 before applying their dead zone
+        for trigger in [GamepadAxis::AxisLeftTrigger, GamepadAxis::AxisRightTrigger] {
+            let value = (snapshot.axes[trigger as usize] + 1.0) / 2.0;
+            snapshot.axes[trigger as usize] = if value <= self.trigger_dead_zone {
+                0.0
+            } else {
+                ((value - self.trigger_dead_zone) / (1.0 - self.trigger_dead_zone)).min(1.0)
+            };
+        }
+
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use crate::engine::input::{input_binding::InputBinding, input_map::InputMap};
+
+    use super::*;
+
+    struct FakeJoystick {
+        name: String,
+        is_gamepad: bool,
+        snapshot: GamepadSnapshot,
+    }
+
+    // Joysticks the test plugs in, pulls out and moves while the manager owns the provider
+    #[derive(Clone, Default)]
+    struct FakeJoystickProvider {
+        joysticks: Rc<RefCell<HashMap<usize, FakeJoystick>>>,
+    }
+
+    impl FakeJoystickProvider {
+        fn connect(&self, index: usize, name: &str, is_gamepad: bool) {
+            let snapshot = Self::at_rest();
+            self.joysticks.borrow_mut().insert(index, FakeJoystick { name: name.to_string(), is_gamepad, snapshot });
+        }
+
+        fn disconnect(&self, index: usize) {
+            self.joysticks.borrow_mut().remove(&index);
+        }
+
+        fn set_button(&self, index: usize, button: GamepadButton, pressed: bool) {
+            self.joysticks.borrow_mut().get_mut(&index).unwrap().snapshot.buttons[button as usize] = pressed;
+        }
+
+        fn set_axis(&self, index: usize, axis: GamepadAxis, value: f32) {
+            self.joysticks.borrow_mut().get_mut(&index).unwrap().snapshot.axes[axis as usize] = value;
+        }
+
+        // Sticks centred and triggers released, as GLFW reports an idle gamepad
+        fn at_rest() -> GamepadSnapshot {
+            let mut snapshot = GamepadSnapshot::default();
+            snapshot.axes[GamepadAxis::AxisLeftTrigger as usize] = -1.0;
+            snapshot.axes[GamepadAxis::AxisRightTrigger as usize] = -1.0;
+            snapshot
+        }
+    }
+
+    impl JoystickProvider for FakeJoystickProvider {
+        fn is_present(&self, index: usize) -> bool {
+            self.joysticks.borrow().contains_key(&index)
+        }
+
+        fn is_gamepad(&self, index: usize) -> bool {
+            self.joysticks.borrow().get(&index).is_some_and(|joystick| joystick.is_gamepad)
+        }
+
+        fn get_name(&self, index: usize) -> Option<String> {
+            self.joysticks.borrow().get(&index).map(|joystick| joystick.name.clone())
+        }
+
+        fn get_gamepad_snapshot(&self, index: usize) -> Option<GamepadSnapshot> {
+            self.joysticks.borrow().get(&index).filter(|joystick| joystick.is_gamepad).map(|joystick| joystick.snapshot)
+        }
+    }
+
+    fn setup() -> (FakeJoystickProvider, GamepadManager, State) {
+        let provider = FakeJoystickProvider::default();
+        let manager = GamepadManager::new(Box::new(provider.clone()));
+        (provider, manager, State::new())
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "expected {}, found {}", expected, actual);
+    }
+
+    #[test]
+    fn connects_and_disconnects_are_reported_once() {
+        let (provider, mut manager, mut state) = setup();
+        assert!(manager.update(&mut state).is_empty());
+
+        provider.connect(2, "Test Pad", true);
+        provider.connect(5, "Flight Stick", false); // No gamepad mapping, so never reported
+        assert_eq!(manager.update(&mut state), vec![GamepadEvent::Connected { index: 2, name: "Test Pad".to_string() }]);
+        assert!(manager.update(&mut state).is_empty());
+        assert_eq!(manager.get_connected_gamepads(), vec![2]);
+        assert!(state.is_gamepad_connected(2));
+        assert!(!state.is_gamepad_connected(5));
+
+        provider.disconnect(2);
+        assert_eq!(manager.update(&mut state), vec![GamepadEvent::Disconnected { index: 2 }]);
+        assert!(manager.update(&mut state).is_empty());
+        assert!(manager.get_connected_gamepads().is_empty());
+        assert!(!state.is_gamepad_connected(2));
+    }
+
+    #[test]
+    fn dead_zones_are_applied_to_sticks_and_triggers() {
+        let (provider, mut manager, mut state) = setup();
+        manager.set_stick_dead_zone(0.2);
+        manager.set_trigger_dead_zone(0.1);
+        provider.connect(0, "Test Pad", true);
+
+        // Inside the radial dead zone both axes read zero
+        provider.set_axis(0, GamepadAxis::AxisLeftX, 0.12);
+        provider.set_axis(0, GamepadAxis::AxisLeftY, 0.12);
+        manager.update(&mut state);
+        assert_eq!(state.get_gamepad_axis(0, GamepadAxis::AxisLeftX), 0.0);
+        assert_eq!(state.get_gamepad_axis(0, GamepadAxis::AxisLeftY), 0.0);
+
+        // Outside it the magnitude is rescaled from the edge of the dead zone, keeping the direction
+        provider.set_axis(0, GamepadAxis::AxisLeftX, 0.6);
+        provider.set_axis(0, GamepadAxis::AxisLeftY, 0.0);
+        provider.set_axis(0, GamepadAxis::AxisRightX, -1.0);
+        manager.update(&mut state);
+        assert_near(state.get_gamepad_axis(0, GamepadAxis::AxisLeftX), 0.5);
+        assert_eq!(state.get_gamepad_axis(0, GamepadAxis::AxisLeftY), 0.0);
+        assert_near(state.get_gamepad_axis(0, GamepadAxis::AxisRightX), -1.0);
+
+        // Triggers are remapped from -1..1 to 0..1, then their dead zone applies
+        assert_eq!(state.get_gamepad_axis(0, GamepadAxis::AxisLeftTrigger), 0.0);
+        provider.set_axis(0, GamepadAxis::AxisLeftTrigger, -0.9);
+        provider.set_axis(0, GamepadAxis::AxisRightTrigger, 0.1);
+        manager.update(&mut state);
+        assert_eq!(state.get_gamepad_axis(0, GamepadAxis::AxisLeftTrigger), 0.0);
+        assert_near(state.get_gamepad_axis(0, GamepadAxis::AxisRightTrigger), 0.5);
+    }
+
+    #[test]
+    fn buttons_and_axes_reach_state_and_bindings() {
+        let (provider, mut manager, mut state) = setup();
+        let mut input_map = InputMap::new();
+        input_map.bind_action("jump", InputBinding::GamepadButton(GamepadButton::ButtonA));
+        input_map.bind_action("fire", InputBinding::GamepadAxis(GamepadAxis::AxisRightTrigger, true));
+        input_map.bind_axis("move_x", vec![InputBinding::GamepadAxis(GamepadAxis::AxisLeftX, true)], vec![InputBinding::GamepadAxis(GamepadAxis::AxisLeftX, false)]);
+        provider.connect(1, "Test Pad", true);
+
+        provider.set_button(1, GamepadButton::ButtonA, true);
+        provider.set_axis(1, GamepadAxis::AxisRightTrigger, 1.0);
+        provider.set_axis(1, GamepadAxis::AxisLeftX, -1.0);
+        state.begin_frame(0.016);
+        manager.update(&mut state);
+
+        assert!(state.is_gamepad_button_pressed(1, GamepadButton::ButtonA));
+        assert!(!state.is_gamepad_button_pressed(1, GamepadButton::ButtonB));
+        assert!(state.is_gamepad_button_just_pressed(1, GamepadButton::ButtonA));
+        assert!(input_map.is_action_just_pressed("jump", &state));
+        assert!(input_map.is_action_pressed("fire", &state));
+        assert_near(input_map.get_axis("move_x", &state), -1.0);
+
+        provider.set_button(1, GamepadButton::ButtonA, false);
+        state.begin_frame(0.016);
+        manager.update(&mut state);
+        assert!(state.is_gamepad_button_just_released(1, GamepadButton::ButtonA));
+        assert!(input_map.is_action_just_released("jump", &state));
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt;
+
+use glfw::{GamepadAxis, GamepadButton, Key, MouseButton};
+use serde::{Deserialize, Serialize};
+
+// Every GLFW key, used to parse key names since glfw::Key has no lookup by name
+const ALL_KEYS: [Key; 121] = [
+    Key::Space, Key::Apostrophe, Key::Comma, Key::Minus, Key::Period, Key::Slash,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::Semicolon, Key::Equal,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::LeftBracket, Key::Backslash, Key::RightBracket, Key::GraveAccent, Key::World1, Key::World2,
+    Key::Escape, Key::Enter, Key::Tab, Key::Backspace, Key::Insert, Key::Delete,
+    Key::Right, Key::Left, Key::Down, Key::Up, Key::PageUp, Key::PageDown, Key::Home, Key::End,
+    Key::CapsLock, Key::ScrollLock, Key::NumLock, Key::PrintScreen, Key::Pause,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12, Key::F13,
+    Key::F14, Key::F15, Key::F16, Key::F17, Key::F18, Key::F19, Key::F20, Key::F21, Key::F22, Key::F23, Key::F24, Key::F25,
+    Key::Kp0, Key::Kp1, Key::Kp2, Key::Kp3, Key::Kp4, Key::Kp5, Key::Kp6, Key::Kp7, Key::Kp8, Key::Kp9,
+    Key::KpDecimal, Key::KpDivide, Key::KpMultiply, Key::KpSubtract, Key::KpAdd, Key::KpEnter, Key::KpEqual,
+    Key::LeftShift, Key::LeftControl, Key::LeftAlt, Key::LeftSuper,
+    Key::RightShift, Key::RightControl, Key::RightAlt, Key::RightSuper, Key::Menu, Key::Unknown,
+];
+
+/// A physical input that can drive an action or axis.
+/// Serialized as a string such as "Key.W", "Mouse.Button1", "GamepadButton.ButtonA" or "GamepadAxis.AxisLeftX+".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum InputBinding {
+    Key(Key),
+    MouseButton(MouseButton),
+    GamepadButton(GamepadButton),
+    GamepadAxis(GamepadAxis, bool), // One direction of an axis, true for the positive half
+}
+
+pub fn key_from_name(name: &str) -> Option<Key> {
+    ALL_KEYS.iter().copied().find(|key| format!("{:?}", key) == name)
+}
+
+pub fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    (0..8).filter_map(MouseButton::from_i32).find(|button| format!("{:?}", button) == name)
+}
+
+pub fn gamepad_button_from_name(name: &str) -> Option<GamepadButton> {
+    (0..15).filter_map(GamepadButton::from_i32).find(|button| format!("{:?}", button) == name)
+}
+
+pub fn gamepad_axis_from_name(name: &str) -> Option<GamepadAxis> {
+    (0..6).filter_map(GamepadAxis::from_i32).find(|axis| format!("{:?}", axis) == name)
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputBinding::Key(key) => write!(f, "Key.{:?}", key),
+            InputBinding::MouseButton(button) => write!(f, "Mouse.{:?}", button),
+            InputBinding::GamepadButton(button) => write!(f, "GamepadButton.{:?}", button),
+            InputBinding::GamepadAxis(axis, positive) => write!(f, "GamepadAxis.{:?}{}", axis, if *positive { "+" } else { "-" }),
+        }
+    }
+}
+
+impl From<InputBinding> for String {
+    fn from(binding: InputBinding) -> Self {
+        binding.to_string()
+    }
+}
+
+impl TryFrom<String> for InputBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (device, name) = value.split_once('.').ok_or_else(|| format!("Invalid input binding '{}'", value))?;
+
+        let binding = match device {
+            "Key" => key_from_name(name).map(InputBinding::Key),
+            "Mouse" => mouse_button_from_name(name).map(InputBinding::MouseButton),
+            "GamepadButton" => gamepad_button_from_name(name).map(InputBinding::GamepadButton),
+            "GamepadAxis" => {
+                if let Some(axis) = name.strip_suffix('+') {
+                    gamepad_axis_from_name(axis).map(|axis| InputBinding::GamepadAxis(axis, true))
+                } else if let Some(axis) = name.strip_suffix('-') {
+                    gamepad_axis_from_name(axis).map(|axis| InputBinding::GamepadAxis(axis, false))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        binding.ok_or_else(|| format!("Unknown input binding '{}'", value))
+    }
+}
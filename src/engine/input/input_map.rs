@@ -0,0 +1,311 @@
+use std::{collections::HashMap, fs::{self, File}, io::{self, Read}};
+
+use glfw::{Action, GamepadAxis, GamepadButton, Key, WindowEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::key_states::State;
+
+use super::{gamepad::{GAMEPAD_AXIS_COUNT, GAMEPAD_BUTTON_COUNT}, input_binding::InputBinding};
+
+// How far a gamepad axis must be pushed before an action bound to it counts as pressed
+pub const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub positive: Vec<InputBinding>,
+    pub negative: Vec<InputBinding>,
+}
+
+// What the next pressed input is bound to
+#[derive(Debug, Clone)]
+enum RebindTarget {
+    Action(String),
+    Axis { axis: String, positive: bool }, // One direction of an axis
+}
+
+// An action or axis direction waiting for the next pressed input to bind to it
+#[derive(Debug, Clone)]
+struct RebindRequest {
+    target: RebindTarget,
+    replace: bool, // Replace the existing bindings instead of adding to them
+}
+
+/// Maps named actions and axes to physical inputs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputMap {
+    #[serde(default)]
+    actions: HashMap<String, Vec<InputBinding>>,
+    #[serde(default)]
+    axes: HashMap<String, AxisBinding>,
+    #[serde(skip)]
+    rebind_request: Option<RebindRequest>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bindings the engine ships with, used when no input map file is available.
+    pub fn engine_defaults() -> Self {
+        let mut input_map = Self::new();
+        input_map.bind_action("rotate_left", InputBinding::Key(Key::Q));
+        input_map.bind_action("rotate_left", InputBinding::GamepadButton(GamepadButton::ButtonLeftBumper));
+        input_map.bind_action("rotate_right", InputBinding::Key(Key::E));
+        input_map.bind_action("rotate_right", InputBinding::GamepadButton(GamepadButton::ButtonRightBumper));
+        input_map.bind_action("resolution_up", InputBinding::Key(Key::KpAdd));
+        input_map.bind_action("resolution_down", InputBinding::Key(Key::KpSubtract));
+        input_map.bind_action("toggle_fullscreen", InputBinding::Key(Key::F11));
+        input_map.bind_action("toggle_borderless", InputBinding::Key(Key::F10));
+        input_map.bind_axis("move_x",
+            vec![InputBinding::Key(Key::D), InputBinding::GamepadAxis(GamepadAxis::AxisLeftX, true)],
+            vec![InputBinding::Key(Key::A), InputBinding::GamepadAxis(GamepadAxis::AxisLeftX, false)]);
+        // GLFW reports stick Y as positive when pushed down
+        input_map.bind_axis("move_y",
+            vec![InputBinding::Key(Key::W), InputBinding::GamepadAxis(GamepadAxis::AxisLeftY, false)],
+            vec![InputBinding::Key(Key::S), InputBinding::GamepadAxis(GamepadAxis::AxisLeftY, true)]);
+        input_map
+    }
+
+    pub fn load_from_json(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(file_path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        let input_map: InputMap = serde_json::from_str(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(input_map)
+    }
+
+    pub fn save_to_json(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(file_path, data)?;
+        Ok(())
+    }
+
+    /// Adds a binding to an action, creating the action if needed.
+    pub fn bind_action(&mut self, action: &str, binding: InputBinding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes one binding from an action. Returns false if it was not bound.
+    pub fn unbind_action(&mut self, action: &str, binding: InputBinding) -> bool {
+        match self.actions.get_mut(action) {
+            Some(bindings) => {
+                let count = bindings.len();
+                bindings.retain(|existing| *existing != binding);
+                bindings.len() != count
+            },
+            None => false,
+        }
+    }
+
+    /// Removes every binding from an action.
+    pub fn clear_action(&mut self, action: &str) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.clear();
+        }
+    }
+
+    pub fn get_action_bindings(&self, action: &str) -> &[InputBinding] {
+        self.actions.get(action).map(|bindings| bindings.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, positive: Vec<InputBinding>, negative: Vec<InputBinding>) {
+        self.axes.insert(axis.to_string(), AxisBinding { positive, negative });
+    }
+
+    /// Adds a binding to one direction of an axis, creating the axis if needed.
+    pub fn bind_axis_direction(&mut self, axis: &str, positive: bool, binding: InputBinding) {
+        let axis_binding = self.axes.entry(axis.to_string()).or_default();
+        let bindings = if positive { &mut axis_binding.positive } else { &mut axis_binding.negative };
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes every binding from one direction of an axis.
+    pub fn clear_axis_direction(&mut self, axis: &str, positive: bool) {
+        if let Some(axis_binding) = self.axes.get_mut(axis) {
+            if positive { axis_binding.positive.clear() } else { axis_binding.negative.clear() }
+        }
+    }
+
+    pub fn get_axis_binding(&self, axis: &str) -> Option<&AxisBinding> {
+        self.axes.get(axis)
+    }
+
+    /// Strength of a single binding in the range 0.0 to 1.0, the strongest across all gamepads.
+    pub fn binding_value(binding: &InputBinding, state: &State) -> f32 {
+        match binding {
+            InputBinding::Key(key) => if state.is_key_pressed(*key) { 1.0 } else { 0.0 },
+            InputBinding::MouseButton(button) => if state.is_mouse_button_pressed(*button) { 1.0 } else { 0.0 },
+            InputBinding::GamepadButton(button) => {
+                if state.get_connected_gamepads().into_iter().any(|index| state.is_gamepad_button_pressed(index, *button)) { 1.0 } else { 0.0 }
+            },
+            InputBinding::GamepadAxis(axis, positive) => state.get_connected_gamepads().into_iter()
+                .map(|index| Self::half_axis(state.get_gamepad_axis(index, *axis), *positive))
+                .fold(0.0, f32::max),
+        }
+    }
+
+    // The part of an axis value pointing in one direction, as a positive number
+    fn half_axis(value: f32, positive: bool) -> f32 {
+        if positive { value.max(0.0) } else { (-value).max(0.0) }
+    }
+
+    // Whether a gamepad axis crossed the press threshold in the given direction since the last frame
+    fn axis_crossed(state: &State, axis: GamepadAxis, positive: bool, pressing: bool) -> bool {
+        state.get_connected_gamepads().into_iter().any(|index| {
+            let previous = Self::half_axis(state.get_previous_gamepad_axis(index, axis), positive) >= AXIS_PRESS_THRESHOLD;
+            let current = Self::half_axis(state.get_gamepad_axis(index, axis), positive) >= AXIS_PRESS_THRESHOLD;
+            if pressing { current && !previous } else { previous && !current }
+        })
+    }
+
+    /// True during the frame the binding went down on any device.
+    pub fn binding_just_pressed(binding: &InputBinding, state: &State) -> bool {
+        match binding {
+            InputBinding::Key(key) => state.is_key_just_pressed(*key),
+            InputBinding::MouseButton(button) => state.is_mouse_button_just_pressed(*button),
+            InputBinding::GamepadButton(button) => state.get_connected_gamepads().into_iter().any(|index| state.is_gamepad_button_just_pressed(index, *button)),
+            InputBinding::GamepadAxis(axis, positive) => Self::axis_crossed(state, *axis, *positive, true),
+        }
+    }
+
+    /// True during the frame the binding went up on any device.
+    pub fn binding_just_released(binding: &InputBinding, state: &State) -> bool {
+        match binding {
+            InputBinding::Key(key) => state.is_key_just_released(*key),
+            InputBinding::MouseButton(button) => state.is_mouse_button_just_released(*button),
+            InputBinding::GamepadButton(button) => state.get_connected_gamepads().into_iter().any(|index| state.is_gamepad_button_just_released(index, *button)),
+            InputBinding::GamepadAxis(axis, positive) => Self::axis_crossed(state, *axis, *positive, false),
+        }
+    }
+
+    /// True while any input bound to the action is held. Gamepad axes count once pushed past half way.
+    pub fn is_action_pressed(&self, action: &str, state: &State) -> bool {
+        self.get_action_bindings(action).iter().any(|binding| Self::binding_value(binding, state) >= AXIS_PRESS_THRESHOLD)
+    }
+
+    /// True during the frame any input bound to the action went down.
+    pub fn is_action_just_pressed(&self, action: &str, state: &State) -> bool {
+        self.get_action_bindings(action).iter().any(|binding| Self::binding_just_pressed(binding, state))
+    }
+
+    /// True during the frame any input bound to the action went up.
+    pub fn is_action_just_released(&self, action: &str, state: &State) -> bool {
+        self.get_action_bindings(action).iter().any(|binding| Self::binding_just_released(binding, state))
+    }
+
+    /// Returns the axis value in the range -1.0 to 1.0, the strongest positive input minus the strongest negative input.
+    pub fn get_axis(&self, axis: &str, state: &State) -> f32 {
+        let Some(axis_binding) = self.axes.get(axis) else {
+            return 0.0;
+        };
+
+        let strongest = |bindings: &Vec<InputBinding>| bindings.iter()
+            .map(|binding| Self::binding_value(binding, state))
+            .fold(0.0, f32::max);
+
+        (strongest(&axis_binding.positive) - strongest(&axis_binding.negative)).clamp(-1.0, 1.0)
+    }
+
+    /// Binds the next pressed key, mouse button or gamepad input to the action. Escape cancels the rebind.
+    pub fn start_rebind(&mut self, action: &str, replace: bool) {
+        self.rebind_request = Some(RebindRequest {
+            target: RebindTarget::Action(action.to_string()),
+            replace,
+        });
+    }
+
+    /// Binds the next pressed input to the positive or negative direction of an axis. Escape cancels the rebind.
+    pub fn start_axis_rebind(&mut self, axis: &str, positive: bool, replace: bool) {
+        self.rebind_request = Some(RebindRequest {
+            target: RebindTarget::Axis { axis: axis.to_string(), positive },
+            replace,
+        });
+    }
+
+    pub fn cancel_rebind(&mut self) {
+        self.rebind_request = None;
+    }
+
+    pub fn is_rebinding(&self) -> bool {
+        self.rebind_request.is_some()
+    }
+
+    /// Offers a window event to a pending rebind. Returns true if the event was consumed,
+    /// in which case it should not also be treated as gameplay input.
+    pub fn capture_input(&mut self, event: &WindowEvent) -> bool {
+        if self.rebind_request.is_none() {
+            return false;
+        }
+
+        let binding = match event {
+            WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                self.cancel_rebind();
+                return true;
+            },
+            WindowEvent::Key(key, _, Action::Press, _) => InputBinding::Key(*key),
+            WindowEvent::MouseButton(button, Action::Press, _) => InputBinding::MouseButton(*button),
+            _ => return false,
+        };
+
+        self.apply_rebind(binding);
+        true
+    }
+
+    /// Offers this frame's gamepad input to a pending rebind. Returns true if a binding was captured.
+    pub fn capture_gamepad_input(&mut self, state: &State) -> bool {
+        if self.rebind_request.is_none() {
+            return false;
+        }
+
+        for index in state.get_connected_gamepads() {
+            let button = (0..GAMEPAD_BUTTON_COUNT as i32)
+                .filter_map(GamepadButton::from_i32)
+                .find(|button| state.is_gamepad_button_just_pressed(index, *button));
+            if let Some(button) = button {
+                self.apply_rebind(InputBinding::GamepadButton(button));
+                return true;
+            }
+
+            for axis in (0..GAMEPAD_AXIS_COUNT as i32).filter_map(GamepadAxis::from_i32) {
+                for positive in [true, false] {
+                    if Self::axis_crossed(state, axis, positive, true) {
+                        self.apply_rebind(InputBinding::GamepadAxis(axis, positive));
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    // Finish the pending rebind with the captured binding
+    fn apply_rebind(&mut self, binding: InputBinding) {
+        let Some(request) = self.rebind_request.take() else {
+            return;
+        };
+        match request.target {
+            RebindTarget::Action(action) => {
+                if request.replace {
+                    self.clear_action(&action);
+                }
+                self.bind_action(&action, binding);
+            },
+            RebindTarget::Axis { axis, positive } => {
+                if request.replace {
+                    self.clear_axis_direction(&axis, positive);
+                }
+                self.bind_axis_direction(&axis, positive, binding);
+            },
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::{collections::HashMap, fs::{self, File}, io::{self, Read}};
+
+use glfw::{Action, Key, Modifiers, Scancode, WindowEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::key_states::State;
+
+use super::{gamepad::GamepadSnapshot, input_binding::InputBinding};
+
+/// A single input, stored in a form that can be written to disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RecordedInput {
+    Button { // Keys and mouse buttons
+        input: InputBinding,
+        action: RecordedAction,
+        #[serde(default)]
+        scancode: Scancode, // Zero for mouse buttons
+        #[serde(default)]
+        modifiers: i32, // Bits of glfw::Modifiers
+    },
+    CursorPos { x: f64, y: f64 },
+    CursorEnter { entered: bool },
+    Scroll { x: f64, y: f64 },
+    Char { character: char },
+    Paste { text: String }, // Clipboard text pasted into the text input
+    Gamepad { index: usize, snapshot: GamepadSnapshot },
+    GamepadRemoved { index: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedAction {
+    Press,
+    Release,
+    Repeat,
+}
+
+impl From<Action> for RecordedAction {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Press => RecordedAction::Press,
+            Action::Release => RecordedAction::Release,
+            Action::Repeat => RecordedAction::Repeat,
+        }
+    }
+}
+
+impl From<RecordedAction> for Action {
+    fn from(action: RecordedAction) -> Self {
+        match action {
+            RecordedAction::Press => Action::Press,
+            RecordedAction::Release => Action::Release,
+            RecordedAction::Repeat => Action::Repeat,
+        }
+    }
+}
+
+impl RecordedInput {
+    /// Converts a window event to a recorded input, or None if it is not an input event.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::Key(key, scancode, action, modifiers) => Some(RecordedInput::Button { input: InputBinding::Key(*key), action: (*action).into(), scancode: *scancode, modifiers: modifiers.bits() }),
+            WindowEvent::MouseButton(button, action, modifiers) => Some(RecordedInput::Button { input: InputBinding::MouseButton(*button), action: (*action).into(), scancode: 0, modifiers: modifiers.bits() }),
+            WindowEvent::CursorPos(x, y) => Some(RecordedInput::CursorPos { x: *x, y: *y }),
+            WindowEvent::CursorEnter(entered) => Some(RecordedInput::CursorEnter { entered: *entered }),
+            WindowEvent::Scroll(x, y) => Some(RecordedInput::Scroll { x: *x, y: *y }),
+            WindowEvent::Char(character) => Some(RecordedInput::Char { character: *character }),
+            _ => None,
+        }
+    }
+
+    /// Rebuilds the window event this input was recorded from. Gamepad and paste inputs have no window event.
+    pub fn to_window_event(&self) -> Option<WindowEvent> {
+        match self {
+            RecordedInput::Button { input: InputBinding::Key(key), action, scancode, modifiers } => {
+                Some(WindowEvent::Key(*key, *scancode, (*action).into(), Modifiers::from_bits_truncate(*modifiers)))
+            },
+            RecordedInput::Button { input: InputBinding::MouseButton(button), action, modifiers, .. } => {
+                Some(WindowEvent::MouseButton(*button, (*action).into(), Modifiers::from_bits_truncate(*modifiers)))
+            },
+            RecordedInput::CursorPos { x, y } => Some(WindowEvent::CursorPos(*x, *y)),
+            RecordedInput::CursorEnter { entered } => Some(WindowEvent::CursorEnter(*entered)),
+            RecordedInput::Scroll { x, y } => Some(WindowEvent::Scroll(*x, *y)),
+            RecordedInput::Char { character } => Some(WindowEvent::Char(*character)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub frame: u64,
+    pub delta_time: f32,
+    pub inputs: Vec<RecordedInput>,
+}
+
+/// Every frame of a session along with the inputs received during it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    pub fn load_from_json(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(file_path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        let recording: InputRecording = serde_json::from_str(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(recording)
+    }
+
+    pub fn save_to_json(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let data = serde_json::to_string(self)?;
+        fs::write(file_path, data)?;
+        Ok(())
+    }
+}
+
+/// Captures input events frame by frame.
+pub struct InputRecorder {
+    recording: InputRecording,
+    last_gamepads: HashMap<usize, GamepadSnapshot>, // Gamepads are only recorded when they change
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self {
+            recording: InputRecording::default(),
+            last_gamepads: HashMap::new(),
+        }
+    }
+
+    /// Starts a new frame. Every frame is recorded, even without input, so its delta time can be replayed.
+    pub fn begin_frame(&mut self, frame: u64, delta_time: f32) {
+        self.recording.frames.push(RecordedFrame {
+            frame,
+            delta_time,
+            inputs: Vec::new(),
+        });
+    }
+
+    fn push(&mut self, input: RecordedInput) {
+        if let Some(frame) = self.recording.frames.last_mut() {
+            frame.inputs.push(input);
+        }
+    }
+
+    /// Records a window event if it is an input event.
+    pub fn record_event(&mut self, event: &WindowEvent) {
+        if let Some(input) = RecordedInput::from_window_event(event) {
+            self.push(input);
+        }
+    }
+
+    /// Records text pasted from the clipboard, since the clipboard may differ when replaying.
+    pub fn record_paste(&mut self, text: &str) {
+        self.push(RecordedInput::Paste { text: text.to_string() });
+    }
+
+    /// Records any gamepad changes since the last call.
+    pub fn record_gamepads(&mut self, state: &State) {
+        let connected = state.get_connected_gamepads();
+
+        let removed: Vec<usize> = self.last_gamepads.keys().copied().filter(|index| !connected.contains(index)).collect();
+        for index in removed {
+            self.last_gamepads.remove(&index);
+            self.push(RecordedInput::GamepadRemoved { index });
+        }
+
+        for index in connected {
+            if let Some(snapshot) = state.get_gamepad_snapshot(index) {
+                if self.last_gamepads.get(&index) != Some(&snapshot) {
+                    self.last_gamepads.insert(index, snapshot);
+                    self.push(RecordedInput::Gamepad { index, snapshot });
+                }
+            }
+        }
+    }
+
+    /// Ends the recording and returns it.
+    pub fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+/// Plays back a recording one frame at a time, delivering each input on the frame it was recorded on.
+pub struct InputReplay {
+    recording: InputRecording,
+    position: usize, // Next recorded frame to deliver
+    frame: Option<u64>, // Recorded frame number of the next call, starting at the first recorded frame
+    delta_time: f32, // Used for frame numbers missing from the recording
+    scancodes: HashMap<Key, Scancode>, // Last replayed scancode per key, for the releases sent at the end
+}
+
+impl InputReplay {
+    pub fn new(recording: InputRecording) -> Self {
+        let delta_time = recording.frames.first().map(|frame| frame.delta_time).unwrap_or_default();
+        Self {
+            recording,
+            position: 0,
+            frame: None,
+            delta_time,
+            scancodes: HashMap::new(),
+        }
+    }
+
+    /// Returns the inputs and timing of the next frame, or None once the recording is exhausted.
+    /// Frame numbers missing from the recording come back empty with the previous frame's delta time,
+    /// and recorded frames sharing a number are delivered together.
+    pub fn next_frame(&mut self) -> Option<RecordedFrame> {
+        let first = self.recording.frames.get(self.position)?;
+        let frame_number = *self.frame.get_or_insert(first.frame);
+
+        let mut frame = RecordedFrame {
+            frame: frame_number,
+            delta_time: self.delta_time,
+            inputs: Vec::new(),
+        };
+        while let Some(recorded) = self.recording.frames.get(self.position).filter(|recorded| recorded.frame <= frame_number) {
+            frame.delta_time = recorded.delta_time;
+            frame.inputs.extend(recorded.inputs.iter().cloned());
+            self.position += 1;
+        }
+
+        for input in &frame.inputs {
+            if let RecordedInput::Button { input: InputBinding::Key(key), scancode, .. } = input {
+                self.scancodes.insert(*key, *scancode);
+            }
+        }
+        self.delta_time = frame.delta_time;
+        self.frame = Some(frame_number + 1);
+        Some(frame)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.recording.frames.len()
+    }
+
+    /// Release events for every key and mouse button still held in State, so nothing stays pressed
+    /// once the replay ends.
+    pub fn release_events(&self, state: &State) -> Vec<WindowEvent> {
+        let keys = state.get_pressed_keys().into_iter()
+            .map(|key| WindowEvent::Key(key, self.scancodes.get(&key).copied().unwrap_or(0), Action::Release, Modifiers::empty()));
+        let buttons = state.get_pressed_mouse_buttons().into_iter()
+            .map(|button| WindowEvent::MouseButton(button, Action::Release, Modifiers::empty()));
+        keys.chain(buttons).collect()
+    }
+
+    /// Applies a recorded gamepad input to State. Returns false for inputs that are window events.
+    pub fn apply_gamepad_input(input: &RecordedInput, state: &mut State) -> bool {
+        match input {
+            RecordedInput::Gamepad { index, snapshot } => state.set_gamepad_snapshot(*index, *snapshot),
+            RecordedInput::GamepadRemoved { index } => state.remove_gamepad(*index),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::MouseButton;
+
+    use super::*;
+
+    fn recorded_frame(frame: u64, delta_time: f32, events: &[WindowEvent]) -> RecordedFrame {
+        RecordedFrame {
+            frame,
+            delta_time,
+            inputs: events.iter().filter_map(RecordedInput::from_window_event).collect(),
+        }
+    }
+
+    #[test]
+    fn key_events_keep_scancodes_and_modifiers() {
+        let event = WindowEvent::Key(Key::V, 47, Action::Press, Modifiers::Control | Modifiers::Shift);
+        let input = RecordedInput::from_window_event(&event).unwrap();
+        let input: RecordedInput = serde_json::from_str(&serde_json::to_string(&input).unwrap()).unwrap();
+
+        assert_eq!(input.to_window_event(), Some(event));
+    }
+
+    #[test]
+    fn inputs_are_delivered_on_their_recorded_frame() {
+        let press = WindowEvent::Key(Key::A, 30, Action::Press, Modifiers::empty());
+        let release = WindowEvent::Key(Key::A, 30, Action::Release, Modifiers::empty());
+        let mut replay = InputReplay::new(InputRecording {
+            frames: vec![recorded_frame(10, 0.1, &[press]), recorded_frame(12, 0.2, &[release])],
+        });
+
+        let frames: Vec<(u64, f32, usize)> = std::iter::from_fn(|| replay.next_frame())
+            .map(|frame| (frame.frame, frame.delta_time, frame.inputs.len()))
+            .collect();
+        assert_eq!(frames, vec![(10, 0.1, 1), (11, 0.1, 0), (12, 0.2, 1)]);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn held_inputs_are_released_when_the_replay_ends() {
+        let mut replay = InputReplay::new(InputRecording {
+            frames: vec![recorded_frame(0, 0.1, &[
+                WindowEvent::Key(Key::W, 17, Action::Press, Modifiers::empty()),
+                WindowEvent::MouseButton(MouseButton::Button1, Action::Press, Modifiers::empty()),
+            ])],
+        });
+        let mut state = State::new();
+        for input in replay.next_frame().unwrap().inputs {
+            state.handle_event(input.to_window_event().unwrap());
+        }
+        assert!(replay.next_frame().is_none());
+
+        for event in replay.release_events(&state) {
+            if let WindowEvent::Key(_, scancode, ..) = event {
+                assert_eq!(scancode, 17);
+            }
+            state.handle_event(event);
+        }
+        assert!(!state.is_key_pressed(Key::W));
+        assert!(!state.is_mouse_button_pressed(MouseButton::Button1));
+    }
+}
@@ -0,0 +1,156 @@
+use glfw::{Action, Key, Modifiers, WindowEvent};
+
+/// An editable line of text fed by typed characters, for name entry fields and consoles.
+/// The cursor is a byte offset that always sits on a character boundary.
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    active: bool, // Only an active input takes characters and editing keys
+    max_length: Option<usize>, // Limit in characters
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text and moves the cursor to the end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        self.cursor = 0;
+        self.insert_str(text);
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Returns the text and clears the input, used when a line is submitted.
+    pub fn take_text(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    /// Returns the cursor position in characters from the start of the text.
+    pub fn get_cursor(&self) -> usize {
+        self.text[..self.cursor].chars().count()
+    }
+
+    /// Inserts text at the cursor. Control characters such as newlines are dropped,
+    /// and anything past the max length is cut off.
+    pub fn insert_str(&mut self, text: &str) {
+        let mut remaining = self.max_length.map(|max_length| max_length.saturating_sub(self.text.chars().count()));
+
+        for character in text.chars().filter(|character| !character.is_control()) {
+            if remaining == Some(0) {
+                break;
+            }
+            self.text.insert(self.cursor, character);
+            self.cursor += character.len_utf8();
+            remaining = remaining.map(|remaining| remaining - 1);
+        }
+    }
+
+    /// Inserts clipboard text at the cursor.
+    pub fn paste(&mut self, text: &str) {
+        self.insert_str(text);
+    }
+
+    /// Removes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if let Some(previous) = self.previous_boundary() {
+            self.text.replace_range(previous..self.cursor, "");
+            self.cursor = previous;
+        }
+    }
+
+    /// Removes the character after the cursor.
+    pub fn delete(&mut self) {
+        if let Some(next) = self.next_boundary() {
+            self.text.replace_range(self.cursor..next, "");
+        }
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        if let Some(previous) = self.previous_boundary() {
+            self.cursor = previous;
+        }
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        if let Some(next) = self.next_boundary() {
+            self.cursor = next;
+        }
+    }
+
+    pub fn move_cursor_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_cursor_end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor].char_indices().next_back().map(|(index, _)| index)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..].chars().next().map(|character| self.cursor + character.len_utf8())
+    }
+
+    /// True for Ctrl+V, or Cmd+V on macOS. The caller reads the clipboard and passes it to paste.
+    pub fn is_paste_shortcut(event: &WindowEvent) -> bool {
+        matches!(event, WindowEvent::Key(Key::V, _, Action::Press | Action::Repeat, modifiers)
+            if modifiers.intersects(Modifiers::Control | Modifiers::Super))
+    }
+
+    /// Applies a typed character or editing key while active. Returns true if the event was used,
+    /// in which case it should not also be treated as gameplay input. While active every key press is used
+    /// except Enter, Escape and Tab, which are left for whatever owns the field. Releases are never used.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        if !self.active {
+            return false;
+        }
+
+        match event {
+            WindowEvent::Char(character) => {
+                self.insert_str(&character.to_string());
+                true
+            },
+            WindowEvent::Key(key, _, Action::Press | Action::Repeat, _) => {
+                match key {
+                    Key::Backspace => self.backspace(),
+                    Key::Delete => self.delete(),
+                    Key::Left => self.move_cursor_left(),
+                    Key::Right => self.move_cursor_right(),
+                    Key::Home => self.move_cursor_home(),
+                    Key::End => self.move_cursor_end(),
+                    Key::Enter | Key::KpEnter | Key::Escape | Key::Tab => return false,
+                    _ => {}, // Typing keys, their text arrives as Char events
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+}
@@ -1,33 +1,243 @@
-use glfw::{Action, Key, WindowEvent};
-use std::collections::HashSet;
+use glfw::{Action, GamepadAxis, GamepadButton, Key, MouseButton, WindowEvent};
+use nalgebra::Vector2;
+use std::collections::{HashMap, HashSet};
+
+use super::{input::gamepad::GamepadSnapshot, window::viewport::Viewport};
 
 pub struct State {
     active_keys: HashSet<Key>, // Track active keys
+    just_pressed_keys: HashSet<Key>, // Keys pressed since the last frame boundary
+    just_released_keys: HashSet<Key>, // Keys released since the last frame boundary
+    key_hold_times: HashMap<Key, f32>, // Seconds each active key has been held
+    active_mouse_buttons: HashSet<MouseButton>, // Track held mouse buttons
+    just_pressed_mouse_buttons: HashSet<MouseButton>,
+    just_released_mouse_buttons: HashSet<MouseButton>,
+    cursor_position: (f64, f64), // Window screen coordinates, origin top left
+    cursor_in_window: bool,
+    has_cursor_position: bool, // False until the first cursor event, so the first position is not counted as movement
+    cursor_delta: (f64, f64), // Cursor movement accumulated since the last frame boundary
+    scroll_delta: (f64, f64), // Scroll accumulated since the last frame boundary
+    text_input: String, // Characters typed since the last frame boundary
+    gamepads: HashMap<usize, GamepadSnapshot>, // Connected gamepads by joystick index, with dead zones applied
+    previous_gamepads: HashMap<usize, GamepadSnapshot>, // Gamepads as of the last frame boundary
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
             active_keys: HashSet::new(),
+            just_pressed_keys: HashSet::new(),
+            just_released_keys: HashSet::new(),
+            key_hold_times: HashMap::new(),
+            active_mouse_buttons: HashSet::new(),
+            just_pressed_mouse_buttons: HashSet::new(),
+            just_released_mouse_buttons: HashSet::new(),
+            cursor_position: (0.0, 0.0),
+            cursor_in_window: false,
+            has_cursor_position: false,
+            cursor_delta: (0.0, 0.0),
+            scroll_delta: (0.0, 0.0),
+            text_input: String::new(),
+            gamepads: HashMap::new(),
+            previous_gamepads: HashMap::new(),
+        }
+    }
+
+    // Mark a frame boundary, called by the event loop before polling events.
+    // Clears last frame's transitions and advances hold times by the frame's delta time.
+    pub fn begin_frame(&mut self, delta_time: f32) {
+        self.just_pressed_keys.clear();
+        self.just_released_keys.clear();
+        self.just_pressed_mouse_buttons.clear();
+        self.just_released_mouse_buttons.clear();
+        self.scroll_delta = (0.0, 0.0);
+        self.cursor_delta = (0.0, 0.0);
+        self.text_input.clear();
+        self.previous_gamepads = self.gamepads.clone();
+
+        for hold_time in self.key_hold_times.values_mut() {
+            *hold_time += delta_time;
+        }
+    }
+
+    // Route a window event to the matching handler
+    pub fn handle_event(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::Key(..) => self.handle_key_event(event),
+            WindowEvent::Char(character) => self.text_input.push(character),
+            _ => self.handle_mouse_event(event),
         }
     }
 
     // Handle window events to update active keys
     pub fn handle_key_event(&mut self, event: WindowEvent) {
         if let WindowEvent::Key(key, _, action, _) = event {
-            match action {
-                Action::Press | Action::Repeat => {
-                    self.active_keys.insert(key);
+            if action == Action::Release {
+                if self.active_keys.remove(&key) {
+                    self.just_released_keys.insert(key);
+                    self.key_hold_times.remove(&key);
+                }
+            } else if self.active_keys.insert(key) {
+                // Repeats of an already held key are not new presses
+                self.just_pressed_keys.insert(key);
+                self.key_hold_times.insert(key, 0.0);
+            }
+        }
+    }
+
+    // Handle window events to update the mouse state
+    pub fn handle_mouse_event(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::MouseButton(button, action, _) => {
+                if action == Action::Release {
+                    if self.active_mouse_buttons.remove(&button) {
+                        self.just_released_mouse_buttons.insert(button);
+                    }
+                } else if self.active_mouse_buttons.insert(button) {
+                    self.just_pressed_mouse_buttons.insert(button);
                 }
-                Action::Release => {
-                    self.active_keys.remove(&key);
+            }
+            WindowEvent::CursorPos(x, y) => {
+                if self.has_cursor_position {
+                    self.cursor_delta.0 += x - self.cursor_position.0;
+                    self.cursor_delta.1 += y - self.cursor_position.1;
                 }
-                _ => {}
+                self.cursor_position = (x, y);
+                self.has_cursor_position = true;
             }
+            WindowEvent::CursorEnter(entered) => {
+                self.cursor_in_window = entered;
+            }
+            WindowEvent::Scroll(x, y) => {
+                self.scroll_delta.0 += x;
+                self.scroll_delta.1 += y;
+            }
+            _ => {}
         }
     }
 
+    // Record the latest state of a gamepad, called by the gamepad manager each frame
+    pub fn set_gamepad_snapshot(&mut self, index: usize, snapshot: GamepadSnapshot) {
+        self.gamepads.insert(index, snapshot);
+    }
+
+    pub fn remove_gamepad(&mut self, index: usize) {
+        self.gamepads.remove(&index);
+    }
+
+    // Every key currently held, in no particular order
+    pub fn get_pressed_keys(&self) -> Vec<Key> {
+        self.active_keys.iter().copied().collect()
+    }
+
+    // Every mouse button currently held, in no particular order
+    pub fn get_pressed_mouse_buttons(&self) -> Vec<MouseButton> {
+        self.active_mouse_buttons.iter().copied().collect()
+    }
+
     pub fn is_key_pressed(&self, key: Key) -> bool {
         self.active_keys.contains(&key)
     }
+
+    // True only during the frame the key went down
+    pub fn is_key_just_pressed(&self, key: Key) -> bool {
+        self.just_pressed_keys.contains(&key)
+    }
+
+    // True only during the frame the key went up
+    pub fn is_key_just_released(&self, key: Key) -> bool {
+        self.just_released_keys.contains(&key)
+    }
+
+    // Seconds the key has been held, or None if it is not held
+    pub fn get_key_hold_duration(&self, key: Key) -> Option<f32> {
+        self.key_hold_times.get(&key).copied()
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.active_mouse_buttons.contains(&button)
+    }
+
+    pub fn is_mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed_mouse_buttons.contains(&button)
+    }
+
+    pub fn is_mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.just_released_mouse_buttons.contains(&button)
+    }
+
+    // Cursor position in window screen coordinates
+    pub fn get_cursor_position(&self) -> (f64, f64) {
+        self.cursor_position
+    }
+
+    // Cursor position in world coordinates, using the viewport's current projection
+    pub fn get_cursor_world_position(&self, viewport: &Viewport) -> Vector2<f32> {
+        viewport.screen_to_world(self.cursor_position.0, self.cursor_position.1)
+    }
+
+    // Cursor movement in screen coordinates since the start of the frame.
+    // Keeps reporting movement while the cursor is locked, which is what mouse look should use.
+    pub fn get_cursor_delta(&self) -> (f64, f64) {
+        self.cursor_delta
+    }
+
+    pub fn is_cursor_in_window(&self) -> bool {
+        self.cursor_in_window
+    }
+
+    // Horizontal and vertical scroll since the start of the frame
+    pub fn get_scroll_delta(&self) -> (f64, f64) {
+        self.scroll_delta
+    }
+
+    /// Returns the characters typed this frame, after keyboard layout and IME processing.
+    pub fn get_text_input(&self) -> &str {
+        &self.text_input
+    }
+
+    pub fn is_gamepad_connected(&self, index: usize) -> bool {
+        self.gamepads.contains_key(&index)
+    }
+
+    // Indices of the connected gamepads in ascending order
+    pub fn get_connected_gamepads(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.gamepads.keys().copied().collect();
+        indices.sort();
+        indices
+    }
+
+    pub fn get_gamepad_snapshot(&self, index: usize) -> Option<GamepadSnapshot> {
+        self.gamepads.get(&index).copied()
+    }
+
+    pub fn is_gamepad_button_pressed(&self, index: usize, button: GamepadButton) -> bool {
+        self.gamepads.get(&index).is_some_and(|gamepad| gamepad.is_button_pressed(button))
+    }
+
+    pub fn is_gamepad_button_just_pressed(&self, index: usize, button: GamepadButton) -> bool {
+        let was_pressed = self.previous_gamepads.get(&index).is_some_and(|gamepad| gamepad.is_button_pressed(button));
+        self.is_gamepad_button_pressed(index, button) && !was_pressed
+    }
+
+    pub fn is_gamepad_button_just_released(&self, index: usize, button: GamepadButton) -> bool {
+        let was_pressed = self.previous_gamepads.get(&index).is_some_and(|gamepad| gamepad.is_button_pressed(button));
+        !self.is_gamepad_button_pressed(index, button) && was_pressed
+    }
+
+    // Axis value after dead zones, sticks range from -1.0 to 1.0 and triggers from 0.0 to 1.0
+    pub fn get_gamepad_axis(&self, index: usize, axis: GamepadAxis) -> f32 {
+        self.gamepads.get(&index).map_or(0.0, |gamepad| gamepad.get_axis(axis))
+    }
+
+    // Axis value as of the last frame boundary
+    pub fn get_previous_gamepad_axis(&self, index: usize, axis: GamepadAxis) -> f32 {
+        self.previous_gamepads.get(&index).map_or(0.0, |gamepad| gamepad.get_axis(axis))
+    }
 }
@@ -1,2 +1,11 @@
+pub mod background_loader;
+pub mod prefab;
 pub mod scene;
-pub mod scene_manager;
\ No newline at end of file
+pub mod scene_data;
+pub mod scene_manager;
+pub mod scene_migration;
+pub mod scene_reload;
+pub mod scene_stack;
+pub mod scene_validation;
+pub mod tiled_import;
+pub mod tiled_map;
\ No newline at end of file
@@ -0,0 +1,226 @@
+use std::{fs, path::PathBuf, sync::{atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}, mpsc::{self, Receiver, Sender, TryRecvError}, Arc, Mutex}, thread};
+
+use crate::engine::graphics::{shader_manager::ShaderManager, texture_manager::{DecodedImage, TextureManager}};
+
+use super::scene_manager::SceneManager;
+
+#[derive(Default)]
+struct LoadCounters {
+    items_total: AtomicUsize,
+    items_done: AtomicUsize,
+    bytes_total: AtomicU64,
+    bytes_done: AtomicU64,
+    listed: AtomicBool, // Set once the totals are known
+    errors: Mutex<Vec<String>>,
+}
+
+/// Shared view of one background load, cheap to clone and safe to read from any thread.
+/// Bytes count file reading, items count textures and scenes that are fully loaded or failed.
+#[derive(Clone, Default)]
+pub struct LoadProgress {
+    counters: Arc<LoadCounters>,
+}
+
+impl LoadProgress {
+    pub fn get_items_done(&self) -> usize {
+        self.counters.items_done.load(Ordering::Acquire)
+    }
+
+    pub fn get_items_total(&self) -> usize {
+        self.counters.items_total.load(Ordering::Acquire)
+    }
+
+    pub fn get_bytes_done(&self) -> u64 {
+        self.counters.bytes_done.load(Ordering::Acquire)
+    }
+
+    pub fn get_bytes_total(&self) -> u64 {
+        self.counters.bytes_total.load(Ordering::Acquire)
+    }
+
+    /// Fraction of items done from 0 to 1, for a loading bar.
+    pub fn get_fraction(&self) -> f32 {
+        if !self.counters.listed.load(Ordering::Acquire) {
+            return 0.0;
+        }
+        match self.get_items_total() {
+            0 => 1.0,
+            total => self.get_items_done() as f32 / total as f32,
+        }
+    }
+
+    /// True once every item is loaded or has failed.
+    pub fn is_finished(&self) -> bool {
+        self.counters.listed.load(Ordering::Acquire) && self.get_items_done() >= self.get_items_total()
+    }
+
+    /// Problems hit so far. Failed items still count as done.
+    pub fn get_errors(&self) -> Vec<String> {
+        self.counters.errors.lock().unwrap().clone()
+    }
+
+    fn add_error(&self, error: String) {
+        self.counters.errors.lock().unwrap().push(error);
+    }
+
+    fn complete_item(&self) {
+        self.counters.items_done.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Textures and scenes to load together. Textures are uploaded before the scenes are built,
+/// so the scenes may use them.
+#[derive(Debug, Clone, Default)]
+pub struct LoadRequest {
+    textures: Vec<(String, PathBuf)>,
+    texture_directories: Vec<String>,
+    scenes: Vec<String>,
+}
+
+impl LoadRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_texture(mut self, name: &str, path: &str) -> Self {
+        self.textures.push((name.to_string(), PathBuf::from(path)));
+        self
+    }
+
+    /// Every image in the directory, named after the file as load_textures_from_directory does.
+    pub fn with_texture_directory(mut self, dir_path: &str) -> Self {
+        self.texture_directories.push(dir_path.to_string());
+        self
+    }
+
+    pub fn with_scene(mut self, file_path: &str) -> Self {
+        self.scenes.push(file_path.to_string());
+        self
+    }
+}
+
+// What a worker hands back for the context thread to finish
+enum LoadedItem {
+    Texture { name: String, image: DecodedImage },
+    Scene { file_path: String, data: String },
+}
+
+struct LoadJob {
+    receiver: Receiver<LoadedItem>,
+    progress: LoadProgress,
+}
+
+/// Loads scenes and textures without blocking the frame. File reading and image decoding run on a worker
+/// thread per request, and update finishes each item on the thread that owns the GL context.
+pub struct BackgroundLoader {
+    jobs: Vec<LoadJob>,
+}
+
+impl Default for BackgroundLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BackgroundLoader {
+    pub fn new() -> Self {
+        Self {
+            jobs: Vec::new(),
+        }
+    }
+
+    /// Starts loading on a worker thread and returns a handle to watch its progress.
+    pub fn start(&mut self, request: LoadRequest) -> LoadProgress {
+        let progress = LoadProgress::default();
+        self.start_with_progress(request, progress.clone());
+        progress
+    }
+
+    /// Starts loading on a worker thread, reporting to a handle that was handed out earlier.
+    pub fn start_with_progress(&mut self, request: LoadRequest, progress: LoadProgress) {
+        let (sender, receiver) = mpsc::channel();
+
+        let worker_progress = progress.clone();
+        thread::spawn(move || Self::read_items(request, sender, worker_progress));
+
+        self.jobs.push(LoadJob { receiver, progress });
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.jobs.is_empty()
+    }
+
+    // Runs on the worker thread. Items are sent in order, textures first, so scenes arrive after their textures.
+    fn read_items(request: LoadRequest, sender: Sender<LoadedItem>, progress: LoadProgress) {
+        let mut textures = request.textures;
+        for dir_path in &request.texture_directories {
+            match TextureManager::list_textures_in_directory(dir_path) {
+                Ok(listed) => textures.extend(listed),
+                Err(e) => progress.add_error(format!("Failed to list textures in '{}': {}", dir_path, e)),
+            }
+        }
+
+        let file_size = |path: &str| fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+        let bytes_total: u64 = textures.iter().map(|(_, path)| file_size(&path.to_string_lossy())).sum::<u64>()
+            + request.scenes.iter().map(|path| file_size(path)).sum::<u64>();
+
+        let counters = &progress.counters;
+        counters.items_total.store(textures.len() + request.scenes.len(), Ordering::Release);
+        counters.bytes_total.store(bytes_total, Ordering::Release);
+        counters.listed.store(true, Ordering::Release);
+
+        for (name, path) in textures {
+            let path = path.to_string_lossy().into_owned();
+            let image = TextureManager::decode_image(&path);
+            counters.bytes_done.fetch_add(file_size(&path), Ordering::AcqRel);
+
+            match image {
+                Ok(image) => {
+                    if sender.send(LoadedItem::Texture { name, image }).is_err() {
+                        return; // The loader was dropped
+                    }
+                },
+                Err(e) => {
+                    progress.add_error(format!("Error loading texture '{}': {}", name, e));
+                    progress.complete_item();
+                },
+            }
+        }
+
+        for file_path in request.scenes {
+            match fs::read_to_string(&file_path) {
+                Ok(data) => {
+                    counters.bytes_done.fetch_add(data.len() as u64, Ordering::AcqRel);
+                    if sender.send(LoadedItem::Scene { file_path, data }).is_err() {
+                        return;
+                    }
+                },
+                Err(e) => {
+                    progress.add_error(format!("Failed to read scene '{}': {}", file_path, e));
+                    progress.complete_item();
+                },
+            }
+        }
+    }
+
+    /// Uploads decoded textures and builds scenes that have been read, called once per frame on the context thread.
+    pub fn update(&mut self, texture_manager: &TextureManager, shader_manager: &ShaderManager, scene_manager: &mut SceneManager) {
+        self.jobs.retain(|job| {
+            loop {
+                match job.receiver.try_recv() {
+                    Ok(LoadedItem::Texture { name, image }) => {
+                        texture_manager.upload_texture(&name, &image);
+                    },
+                    Ok(LoadedItem::Scene { file_path, data }) => {
+                        if let Err(e) = scene_manager.load_scene_from_str(&file_path, &data, texture_manager, shader_manager) {
+                            job.progress.add_error(format!("Failed to load scene '{}': {}", file_path, e));
+                        }
+                    },
+                    Err(TryRecvError::Empty) => return true,
+                    Err(TryRecvError::Disconnected) => return false, // The worker is done and everything it sent is handled
+                }
+                job.progress.complete_item();
+            }
+        });
+    }
+}
@@ -0,0 +1,72 @@
+use nalgebra::{Rotation2, Vector2};
+
+use super::scene_data::{ObjectData, PrefabInstance};
+
+/// A reusable object or group of objects, loaded from a file in the scene format.
+/// Object positions are relative to the prefab's origin.
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    name: String,
+    objects: Vec<ObjectData>,
+}
+
+impl Prefab {
+    pub fn new(name: &str, objects: Vec<ObjectData>) -> Self {
+        Self {
+            name: name.to_string(),
+            objects,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_objects(&self) -> &[ObjectData] {
+        &self.objects
+    }
+
+    /// Names the objects of an instance would get. A single object prefab takes the instance name,
+    /// objects in a group are named instance/object. The separator differs from the scene.object
+    /// namespacing of clashing scene objects, so the two cannot produce the same name.
+    pub fn object_names(&self, instance_name: &str) -> Vec<String> {
+        if self.objects.len() == 1 {
+            vec![instance_name.to_string()]
+        } else {
+            self.objects.iter().map(|obj| format!("{}/{}", instance_name, obj.name)).collect()
+        }
+    }
+
+    /// Returns the object data for one instance with its overrides applied.
+    /// A position must be [x, y] or [x, y, z], an [x, y] position is placed at z = 0.
+    pub fn instantiate(&self, instance: &PrefabInstance, instance_name: &str) -> Result<Vec<ObjectData>, String> {
+        let origin = match instance.position.as_deref() {
+            None => [0.0, 0.0, 0.0],
+            Some([x, y]) => [*x, *y, 0.0],
+            Some([x, y, z]) => [*x, *y, *z],
+            Some(position) => return Err(format!("Position of instance '{}' must be [x, y] or [x, y, z], found {} values", instance_name, position.len())),
+        };
+        let rotation = instance.rotation.unwrap_or(0.0);
+        let scale = instance.scale.unwrap_or(1.0);
+
+        Ok(self.objects.iter().zip(self.object_names(instance_name)).map(|(obj, name)| {
+            let mut obj = obj.clone();
+            obj.name = name;
+
+            // Place the object relative to the instance's origin, rotation and scale
+            let local = Vector2::new(obj.position[0], obj.position[1]);
+            let placed = Rotation2::new(rotation) * local * scale;
+            obj.position = vec![origin[0] + placed.x, origin[1] + placed.y, origin[2] + obj.position[2]];
+            obj.rotation += rotation;
+            obj.scale *= scale;
+
+            if let Some(texture_name) = &instance.texture_name {
+                obj.texture_name = texture_name.clone();
+            }
+            for (key, value) in &instance.properties {
+                obj.properties.insert(key.clone(), value.clone());
+            }
+            obj
+        }).collect())
+    }
+}
@@ -5,12 +5,20 @@ use crate::engine::graphics::internal_object::graphics_object::Generic2DGraphics
 
 pub struct Scene {
     objects: Vec<Arc<RwLock<Generic2DGraphicsObject>>>, // Use Generic2DGraphicsObject for static objects
+    properties: HashMap<String, serde_json::Value>, // Custom values for the whole scene, such as those on an imported map
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Scene {
     pub fn new() -> Self {
         Scene {
             objects: Vec::new(),
+            properties: HashMap::new(),
         }
     }
 
@@ -22,4 +30,16 @@ impl Scene {
     pub fn get_objects(&self) -> &Vec<Arc<RwLock<Generic2DGraphicsObject>>> {
         &self.objects
     }
+
+    pub fn set_property(&mut self, key: &str, value: serde_json::Value) {
+        self.properties.insert(key.to_string(), value);
+    }
+
+    pub fn get_property(&self, key: &str) -> Option<&serde_json::Value> {
+        self.properties.get(key)
+    }
+
+    pub fn get_properties(&self) -> &HashMap<String, serde_json::Value> {
+        &self.properties
+    }
 }
@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, HashSet};
+
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::engine::graphics::{internal_object::graphics_object::{CollisionMode, DrawMode, Generic2DGraphicsObject}, shader_manager::{ShaderDefinition, ShaderManager}, texture_manager::TextureManager};
+
+/// One object as written in scene and prefab files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjectData {
+    pub name: String,
+    pub vertex_data: Vec<f32>,
+    pub texture_coords: Vec<f32>,
+    pub shader: String, // Name of a shader in the scene's shaders section or the shader library
+    pub position: Vec<f32>,  // [x, y, z]
+    pub rotation: f32,
+    pub scale: f32,
+    pub texture_name: String,
+    pub collision_modes: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, Value>, // Game specific values the engine passes through untouched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draw_mode: Option<String>, // TriangleFan when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>, // Visible when not given
+}
+
+/// A prefab placed in a scene or spawned at runtime. Anything left as None keeps the prefab's value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrefabInstance {
+    pub prefab: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>, // Generated if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<Vec<f32>>, // Where the prefab's origin is placed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f32>, // Rotates the whole prefab about its origin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f32>, // Scales the whole prefab about its origin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture_name: Option<String>, // Replaces the texture of every object in the prefab
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, Value>, // Merged over each object's properties
+}
+
+impl PrefabInstance {
+    pub fn new(prefab: &str) -> Self {
+        Self {
+            prefab: prefab.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_position(mut self, position: Vector3<f32>) -> Self {
+        self.position = Some(vec![position.x, position.y, position.z]);
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn with_texture(mut self, texture_name: &str) -> Self {
+        self.texture_name = Some(texture_name.to_string());
+        self
+    }
+
+    pub fn with_property(mut self, key: &str, value: Value) -> Self {
+        self.properties.insert(key.to_string(), value);
+        self
+    }
+}
+
+/// The contents of a scene or prefab file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneData {
+    #[serde(default)]
+    pub version: u64, // Format version, files without one are version 0
+    #[serde(default)]
+    pub shaders: BTreeMap<String, ShaderDefinition>,
+    pub objects: Vec<ObjectData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instances: Vec<PrefabInstance>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, Value>, // Custom values for the whole scene, such as those on an imported map
+}
+
+impl ObjectData {
+    /// Captures an object's current state, failing if it was not created from a named shader.
+    pub fn from_object(obj: &Generic2DGraphicsObject) -> Result<Self, String> {
+        let shader = obj.get_shader_name()
+            .ok_or_else(|| format!("Object '{}' has no shader name to save", obj.get_name()))?;
+
+        let mut collision_modes: Vec<String> = obj.get_collision_modes().iter().map(|mode| format!("{:?}", mode)).collect();
+        collision_modes.sort();
+
+        let position = obj.get_position();
+
+        Ok(Self {
+            name: obj.get_name().to_string(),
+            vertex_data: obj.get_vertex_data().to_vec(),
+            texture_coords: obj.get_texture_coords().to_vec(),
+            shader: shader.to_string(),
+            position: vec![position.x, position.y, position.z],
+            rotation: obj.get_rotation(),
+            scale: obj.get_scale(),
+            texture_name: obj.get_texture_name().unwrap_or_default().to_string(),
+            collision_modes,
+            properties: obj.get_properties().iter().map(|(key, value)| (key.clone(), value.clone())).collect(),
+            draw_mode: Some(format!("{:?}", obj.get_draw_mode())),
+            visible: (!obj.is_visible()).then_some(false),
+        })
+    }
+
+    /// Creates the graphics object with a shader from the shader library. The data is expected to have been validated.
+    pub fn build(self, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<Generic2DGraphicsObject, String> {
+        self.build_with_shaders(texture_manager, shader_manager, &BTreeMap::new())
+    }
+
+    /// Creates the graphics object, looking its shader up in the given shaders before the shader library.
+    /// Used to build a scene before its shaders section is added to the library.
+    pub fn build_with_shaders(self, texture_manager: &TextureManager, shader_manager: &ShaderManager, shaders: &BTreeMap<String, ShaderDefinition>) -> Result<Generic2DGraphicsObject, String> {
+        // Objects sharing a shader share one compiled program
+        let shader_program = match shaders.get(&self.shader) {
+            Some(definition) => shader_manager.get_program_for_definition(definition)?,
+            None => shader_manager.get_program(&self.shader)?,
+        };
+
+        let collision_modes: HashSet<CollisionMode> = self.collision_modes.iter()
+            .filter_map(|collision_mode| CollisionMode::from_name(collision_mode))
+            .collect();
+
+        let position = match self.position[..] {
+            [x, y, z] => Vector3::new(x, y, z),
+            _ => return Err(format!("Object '{}' needs a position of [x, y, z]", self.name)),
+        };
+
+        // An empty texture name means the object is untextured
+        let texture_id = texture_manager.get_texture_id(&self.texture_name);
+
+        let mut graphics_object = Generic2DGraphicsObject::new(
+            self.name,
+            self.vertex_data,
+            self.texture_coords,
+            shader_program,
+            position,
+            self.rotation,
+            self.scale,
+            texture_id,
+            collision_modes,
+        );
+        graphics_object.set_texture_name(Some(self.texture_name));
+        graphics_object.set_shader_name(Some(self.shader));
+        if let Some(draw_mode) = self.draw_mode.as_deref().and_then(DrawMode::from_name) {
+            graphics_object.set_draw_mode(draw_mode);
+        }
+        graphics_object.set_visible(self.visible.unwrap_or(true));
+        for (key, value) in self.properties {
+            graphics_object.set_property(&key, value);
+        }
+
+        Ok(graphics_object)
+    }
+}
@@ -1,20 +1,33 @@
-use std::{collections::{HashMap, HashSet}, fs::File, path::Path, sync::{Arc, RwLock}};
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs::{self, File}, path::Path, sync::{Arc, RwLock}};
 
-use nalgebra::Vector3;
-use serde::Deserialize;
 use std::io::{self, Read};
-use crate::engine::graphics::{internal_object::{graphics_object::{CollisionMode, Generic2DGraphicsObject}, custom_shader::CustomShader}, texture_manager::TextureManager};
+use crate::engine::events::event_bus::{EngineEvent, EventBus};
+use crate::engine::graphics::{internal_object::graphics_object::Generic2DGraphicsObject, shader_manager::ShaderManager, texture_manager::TextureManager, util::master_graphics_list::{MasterGraphicsList, NameCollision}};
 
-use super::scene::Scene;
+use super::{prefab::Prefab, scene::Scene, scene_data::{ObjectData, PrefabInstance, SceneData}, scene_migration::CURRENT_SCENE_VERSION, scene_reload::{self, HotReload, SceneFile}, scene_stack::{SceneHooks, SceneStack, SceneTransition}, scene_validation, tiled_import::{self, TiledImportOptions}, tiled_map::TiledMap};
 
 pub struct SceneManager {
     scenes: HashMap<String, Arc<RwLock<Scene>>>, // Use RwLock for thread safety
+    prefabs: HashMap<String, Prefab>,
+    stack: SceneStack, // Scenes currently in the master graphics list
+    scene_files: HashMap<String, SceneFile>, // Scenes loaded from files, by scene name
+    hot_reload: HotReload,
+}
+
+impl Default for SceneManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SceneManager {
     pub fn new() -> Self {
         Self {
             scenes: HashMap::new(),
+            prefabs: HashMap::new(),
+            stack: SceneStack::new(),
+            scene_files: HashMap::new(),
+            hot_reload: HotReload::new(),
         }
     }
 
@@ -30,93 +43,666 @@ impl SceneManager {
 
     /// Removes a scene by its name.
     pub fn remove_scene(&mut self, name: &str) -> Option<Arc<RwLock<Scene>>> {
+        self.scene_files.remove(name);
         self.scenes.remove(name)
     }
 
+    /// The file a scene was loaded from, or None for scenes not loaded from a scene file.
+    pub fn get_scene_file_path(&self, name: &str) -> Option<&str> {
+        self.scene_files.get(name).map(|scene_file| scene_file.get_path())
+    }
+
     /// Lists all scene names.
     pub fn list_scenes(&self) -> Vec<String> {
         self.scenes.keys().cloned().collect()
     }
 
-    pub fn load_scene_from_json(&mut self, file_path: &str, texture_manager: &TextureManager) -> Result<(), Box<dyn std::error::Error>> {
-        let mut file = File::open(file_path)?;
-        let mut data = String::new();
-        file.read_to_string(&mut data)?;
+    /// Puts a loaded scene on top of the scene stack, adding its objects to the world.
+    /// Scenes already on the stack stay in the world underneath it.
+    pub fn push_scene(&mut self, name: &str, transition: SceneTransition, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> Result<(), String> {
+        let scene = self.get_scene(name).ok_or_else(|| format!("Scene '{}' is not loaded", name))?;
+        self.stack.push(name, scene, transition, master_graphics_list, event_bus);
+        Ok(())
+    }
+
+    /// Takes the top scene off the scene stack, removing its objects from the world. Returns its name.
+    pub fn pop_scene(&mut self, transition: SceneTransition, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> Option<String> {
+        self.stack.pop(transition, master_graphics_list, event_bus)
+    }
+
+    /// Swaps the top scene of the scene stack for another loaded scene.
+    pub fn replace_scene(&mut self, name: &str, transition: SceneTransition, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> Result<(), String> {
+        let scene = self.get_scene(name).ok_or_else(|| format!("Scene '{}' is not loaded", name))?;
+        self.stack.replace(name, scene, transition, master_graphics_list, event_bus);
+        Ok(())
+    }
+
+    /// Pops every scene off the scene stack at once, running their exit hooks.
+    pub fn clear_scene_stack(&mut self, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        self.stack.clear(master_graphics_list, event_bus);
+    }
+
+    /// Advances any running scene transition, called once per frame.
+    pub fn update_transition(&mut self, delta_time: f32, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        self.stack.update(delta_time, master_graphics_list, event_bus);
+    }
+
+    /// Takes a scene out of the world and forgets it. Only objects loaded from that scene are removed,
+    /// and their GL buffers are freed once nothing else holds the objects.
+    pub fn unload_scene(&mut self, name: &str, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> Result<(), String> {
+        let on_stack = self.stack.remove(name, master_graphics_list, event_bus);
+        let scene = self.remove_scene(name);
+        if !on_stack && scene.is_none() {
+            return Err(format!("Scene '{}' is not loaded", name));
+        }
+
+        for object_name in master_graphics_list.remove_scene_objects(name) {
+            event_bus.publish(EngineEvent::ObjectDespawned(object_name));
+        }
+        Ok(())
+    }
+
+    /// Sets how objects pushed onto the scene stack are named when they clash with live objects.
+    /// By default clashing objects are namespaced as scene_name.object_name.
+    pub fn set_name_collision(&mut self, name_collision: NameCollision) {
+        self.stack.set_name_collision(name_collision);
+    }
+
+    pub fn get_name_collision(&self) -> NameCollision {
+        self.stack.get_name_collision()
+    }
+
+    /// Sets the enter and exit hooks for a scene, replacing any it had.
+    pub fn set_scene_hooks(&mut self, name: &str, hooks: SceneHooks) {
+        self.stack.set_hooks(name, hooks);
+    }
+
+    /// Scene names on the stack from the bottom to the top.
+    pub fn get_scene_stack(&self) -> Vec<String> {
+        self.stack.get_scene_names()
+    }
+
+    /// The scene on top of the stack.
+    pub fn get_active_scene(&self) -> Option<&str> {
+        self.stack.get_top_scene()
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        self.stack.is_transitioning()
+    }
+
+    /// Loads a scene file, named after the file. Older versions of the format are upgraded, then the file
+    /// is validated and every problem found is returned together as a SceneValidationErrors.
+    /// Objects refer by name to shaders in the scene's shaders section or already in the shader library.
+    /// The shaders section is added to the library once the scene has loaded, a shader that would replace
+    /// a library shader of the same name with a different definition is an error unless this file defined it.
+    /// Prefab instances are expanded into their objects.
+    pub fn load_scene_from_json(&mut self, file_path: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<(), Box<dyn std::error::Error>> {
+        let data = Self::read_file(file_path)?;
+        self.load_scene_from_str(file_path, &data, texture_manager, shader_manager)
+    }
+
+    /// Loads a scene from file contents that have already been read, such as by the background loader.
+    /// The file path is used for the scene name and error locations.
+    pub fn load_scene_from_str(&mut self, file_path: &str, data: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<(), Box<dyn std::error::Error>> {
+        let scene_name = Self::scene_name_from_path(file_path);
+        self.check_scene_name(&scene_name, file_path)?;
+
+        let scene_data = self.expand_scene_data(file_path, data, texture_manager, shader_manager)?;
+        let json_scene = Self::build_scene(&scene_data, texture_manager, shader_manager)?;
+        shader_manager.register_shaders(file_path, &scene_data.shaders)?;
     
-        let scene_data: SceneData = serde_json::from_str(&data)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.scene_files.insert(scene_name.clone(), SceneFile::new(file_path, scene_data.objects));
+        self.add_scene(scene_name, json_scene);
     
+        Ok(())
+    }
+
+    /// Imports a Tiled map (.tmj or .tmx) as a scene named after the file. Tileset images are loaded into the
+    /// texture manager if they are not already, and the map's custom properties become the scene's properties.
+    /// The map is watched and imported again on reload like a scene file.
+    pub fn load_tiled_map(&mut self, file_path: &str, options: &TiledImportOptions, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<(), Box<dyn std::error::Error>> {
+        let scene_name = Self::scene_name_from_path(file_path);
+        self.check_scene_name(&scene_name, file_path)?;
+
+        let scene_data = Self::import_tiled_map(file_path, options, texture_manager, shader_manager)?;
+        let scene = Self::build_scene(&scene_data, texture_manager, shader_manager)?;
+
+        self.scene_files.insert(scene_name.clone(), SceneFile::new(file_path, scene_data.objects).with_tiled_options(options.clone()));
+        self.add_scene(scene_name, scene);
+        Ok(())
+    }
+
+    // Convert a Tiled map to the scene format
+    fn import_tiled_map(file_path: &str, options: &TiledImportOptions, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<SceneData, Box<dyn std::error::Error>> {
+        if !shader_manager.has_shader(&options.shader) {
+            return Err(format!("Shader '{}' is not in the shader library", options.shader).into());
+        }
+
+        let map = TiledMap::load(file_path)?;
+        let import = tiled_import::import_tiled_map(&map, options, texture_manager)?;
+
+        Ok(SceneData {
+            version: CURRENT_SCENE_VERSION,
+            shaders: BTreeMap::new(),
+            objects: import.objects,
+            instances: Vec::new(),
+            properties: import.properties,
+        })
+    }
+
+    /// Whether a scene was loaded from the given file, however the path to it is written.
+    pub fn is_loaded_from(&self, name: &str, file_path: &str) -> bool {
+        self.scene_files.get(name).is_some_and(|scene_file| {
+            let loaded_path = scene_file.get_path();
+            loaded_path == file_path || matches!((fs::canonicalize(loaded_path), fs::canonicalize(file_path)), (Ok(a), Ok(b)) if a == b)
+        })
+    }
+
+    // Scenes are named after their file, so a level1.json and a level1.tmx cannot both be loaded
+    fn check_scene_name(&self, name: &str, file_path: &str) -> Result<(), String> {
+        if self.scenes.contains_key(name) && !self.is_loaded_from(name, file_path) {
+            return Err(format!("A scene named '{}' is already loaded from another file", name));
+        }
+        Ok(())
+    }
+
+    fn scene_name_from_path(file_path: &str) -> String {
+        Path::new(file_path)
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Unnamed")
+            .to_string()
+    }
+
+    // Validate a scene file's contents and expand its prefab instances into plain objects
+    fn expand_scene_data(&self, file_path: &str, data: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<SceneData, Box<dyn std::error::Error>> {
+        let mut scene_data = self.parse_scene_data(file_path, data, texture_manager, shader_manager)?;
+
+        let mut used_names: HashSet<String> = scene_data.objects.iter().map(|obj| obj.name.clone()).collect();
+        used_names.extend(scene_data.instances.iter().filter_map(|instance| instance.name.clone()));
+
+        for instance in std::mem::take(&mut scene_data.instances) {
+            let prefab = self.prefabs.get(&instance.prefab).ok_or_else(|| format!("Prefab '{}' is not loaded", instance.prefab))?;
+            let instance_name = match &instance.name {
+                Some(name) => name.clone(),
+                None => Self::unique_name(&instance.prefab, |name| used_names.contains(name)),
+            };
+            used_names.insert(instance_name.clone());
+            scene_data.objects.extend(prefab.instantiate(&instance, &instance_name)?);
+        }
+
+        Ok(scene_data)
+    }
+
+    // Objects use the scene's shaders over the shader library, so a scene can be built before its shaders are registered
+    fn build_scene(scene_data: &SceneData, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<Scene, String> {
         let mut json_scene = Scene::new();
-    
-        for obj_data in scene_data.objects {
-            let json_shader = CustomShader::new(
-                &obj_data.vertex_shader,
-                &obj_data.fragment_shader,
-            );
-    
-            let mut json_collision_modes = HashSet::new();
-            for collision_mode in obj_data.collision_modes {
-                match collision_mode.as_str() {
-                    "AABB" => { json_collision_modes.insert(CollisionMode::AABB); }
-                    "Circle" => { json_collision_modes.insert(CollisionMode::Circle); }
-                    "OBB" => { json_collision_modes.insert(CollisionMode::OBB); }
-                    _ => {}
-                }
-            }
-    
-            let position = Vector3::new(
-                obj_data.position[0],
-                obj_data.position[1],
-                obj_data.position[2],
-            );
-    
-            let texture_id = texture_manager.get_texture_id(&obj_data.texture_name);
-    
-            let graphics_object = Generic2DGraphicsObject::new(
-                obj_data.name,
-                obj_data.vertex_data,
-                obj_data.texture_coords,
-                json_shader.get_shader_program(),
-                position,
-                obj_data.rotation,
-                obj_data.scale,
-                texture_id,
-                json_collision_modes,
-            );
+        for obj_data in &scene_data.objects {
+            let graphics_object = obj_data.clone().build_with_shaders(texture_manager, shader_manager, &scene_data.shaders)?;
     
             graphics_object.print_debug();
             let wrapped_object = Arc::new(RwLock::new(graphics_object));
     
             json_scene.add_object(wrapped_object);
         }
-    
-        let scene_name = Path::new(file_path)
+        for (key, value) in &scene_data.properties {
+            json_scene.set_property(key, value.clone());
+        }
+        Ok(json_scene)
+    }
+
+    /// Sets how often scene files are checked for changes in seconds, None turns hot reloading off.
+    pub fn set_hot_reload_interval(&mut self, interval: Option<f32>) {
+        self.hot_reload.set_interval(interval);
+    }
+
+    pub fn get_hot_reload_interval(&self) -> Option<f32> {
+        self.hot_reload.get_interval()
+    }
+
+    /// Reloads any scene whose file has changed once the hot reload interval has passed, called once per frame.
+    /// A file that fails to load leaves its scene as it was, the problems are returned for reporting.
+    pub fn poll_scene_files(&mut self, delta_time: f32, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Vec<String> {
+        if !self.hot_reload.is_due(delta_time) {
+            return Vec::new();
+        }
+
+        let changed: Vec<String> = self.scene_files.iter_mut()
+            .filter_map(|(name, file)| file.check_changed().then(|| name.clone()))
+            .collect();
+
+        changed.into_iter().filter_map(|name| {
+            self.reload_scene(&name, master_graphics_list, event_bus, texture_manager, shader_manager)
+                .err()
+                .map(|e| format!("Failed to reload scene '{}': {}", name, e))
+        }).collect()
+    }
+
+    /// Reads a scene's file or Tiled map again and brings its live objects up to date in place, keeping runtime changes
+    /// the edit did not touch. If the scene is on the stack, added objects are spawned and removed ones despawned.
+    pub fn reload_scene(&mut self, name: &str, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<(), Box<dyn std::error::Error>> {
+        let scene_file = self.scene_files.get(name).ok_or_else(|| format!("Scene '{}' was not loaded from a file", name))?;
+        let file_path = scene_file.get_path().to_string();
+        let scene_data = match scene_file.get_tiled_options().cloned() {
+            Some(options) => Self::import_tiled_map(&file_path, &options, texture_manager, shader_manager)?,
+            None => {
+                let data = Self::read_file(&file_path)?;
+                self.expand_scene_data(&file_path, &data, texture_manager, shader_manager)?
+            },
+        };
+        let scene = Self::build_scene(&scene_data, texture_manager, shader_manager)?;
+
+        // Objects using a shader the edit redefined need its new program even if they did not change
+        let redefined: HashSet<String> = scene_data.shaders.iter()
+            .filter(|(name, definition)| shader_manager.get_shader_definition(name).is_some_and(|existing| existing != **definition))
+            .map(|(name, _)| name.clone())
+            .collect();
+        shader_manager.register_shaders(&file_path, &scene_data.shaders)?;
+        let new_objects = scene_data.objects;
+
+        let old_objects = self.scene_files[name].get_objects().to_vec();
+        let on_stack = self.stack.contains(name);
+
+        for (new, rebuilt) in new_objects.iter().zip(scene.get_objects()) {
+            let rebuilt = rebuilt.read().unwrap();
+            match old_objects.iter().find(|old| old.name == new.name) {
+                Some(old) if old == new && !redefined.contains(&new.shader) => {},
+                Some(old) => {
+                    for live_name in master_graphics_list.find_scene_object(name, &new.name) {
+                        if let Some(live) = master_graphics_list.get_object(&live_name) {
+                            scene_reload::apply_object_changes(&mut live.write().unwrap(), old, new, &rebuilt, redefined.contains(&new.shader));
+                        }
+                    }
+                },
+                None if on_stack => {
+                    let live_name = master_graphics_list.add_scene_object(name, rebuilt.clone(), self.stack.get_name_collision());
+                    self.stack.track_object(name, &live_name);
+                    event_bus.publish(EngineEvent::ObjectSpawned(live_name));
+                },
+                None => {},
+            }
+        }
+
+        for old in old_objects.iter().filter(|old| !new_objects.iter().any(|new| new.name == old.name)) {
+            for live_name in master_graphics_list.find_scene_object(name, &old.name) {
+                master_graphics_list.remove_object(&live_name);
+                self.stack.untrack_object(&live_name);
+                event_bus.publish(EngineEvent::ObjectDespawned(live_name));
+            }
+        }
+
+        if let Some(file) = self.scene_files.get_mut(name) {
+            file.set_objects(new_objects);
+        }
+        self.add_scene(name.to_string(), scene);
+        event_bus.publish(EngineEvent::SceneReloaded(name.to_string()));
+
+        Ok(())
+    }
+
+    fn read_file(file_path: &str) -> io::Result<String> {
+        let mut file = File::open(file_path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+        Ok(data)
+    }
+
+    // Read, upgrade and validate a scene or prefab file
+    fn read_scene_data(&self, file_path: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<SceneData, Box<dyn std::error::Error>> {
+        let data = Self::read_file(file_path)?;
+        self.parse_scene_data(file_path, &data, texture_manager, shader_manager)
+    }
+
+    // Upgrade and validate the contents of a scene or prefab file. Its shaders are left for the caller
+    // to register once the rest of the load has succeeded.
+    fn parse_scene_data(&self, file_path: &str, data: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<SceneData, Box<dyn std::error::Error>> {
+        let document = scene_validation::validate_scene_json(file_path, data, texture_manager, shader_manager, &self.prefabs)?;
+        let scene_data: SceneData = serde_json::from_value(document)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(scene_data)
+    }
+
+    /// Loads a prefab file, named after the file. Prefabs use the scene format but cannot contain prefab instances.
+    pub fn load_prefab_from_json(&mut self, file_path: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<(), Box<dyn std::error::Error>> {
+        let scene_data = self.read_scene_data(file_path, texture_manager, shader_manager)?;
+        if !scene_data.instances.is_empty() {
+            return Err(format!("Prefab '{}' cannot contain prefab instances", file_path).into());
+        }
+        shader_manager.register_shaders(file_path, &scene_data.shaders)?;
+
+        let name = Path::new(file_path)
             .file_stem()
             .and_then(|name| name.to_str())
             .unwrap_or("Unnamed")
             .to_string();
-    
-        self.add_scene(scene_name, json_scene);
-    
+
+        self.prefabs.insert(name.clone(), Prefab::new(&name, scene_data.objects));
         Ok(())
     }
-}
 
-#[derive(Deserialize)]
-struct ObjectData {
-    name: String,
-    vertex_data: Vec<f32>,
-    texture_coords: Vec<f32>,
-    vertex_shader: String,
-    fragment_shader: String,
-    position: Vec<f32>,  // [x, y, z]
-    rotation: f32,
-    scale: f32,
-    texture_name: String,
-    collision_modes: Vec<String>,
+    /// Loads every .json file in a directory as a prefab.
+    pub fn load_prefabs_from_directory(&mut self, dir_path: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<(), Box<dyn std::error::Error>> {
+        for entry in fs::read_dir(dir_path)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|extension| extension == "json") {
+                let path = path.to_str().ok_or("Invalid file name")?;
+                self.load_prefab_from_json(path, texture_manager, shader_manager)
+                    .map_err(|e| format!("Error loading prefab '{}': {}", path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_prefab(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Lists all prefab names.
+    pub fn list_prefabs(&self) -> Vec<String> {
+        self.prefabs.keys().cloned().collect()
+    }
+
+    // The first of base_1, base_2, ... that is not taken
+    fn unique_name(base: &str, is_taken: impl Fn(&str) -> bool) -> String {
+        (1..).map(|index| format!("{}_{}", base, index)).find(|name| !is_taken(name)).unwrap()
+    }
+
+    /// Creates a prefab instance in the master graphics list and returns the names of the new objects.
+    /// The instance gets a unique name unless one is given, a given name that is already in use is an error.
+    /// Each new object is announced with an ObjectSpawned event.
+    /// A position that is not [x, y] or [x, y, z], or a texture that is not loaded, is an error.
+    pub fn spawn_prefab(&self, instance: &PrefabInstance, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus, texture_manager: &TextureManager, shader_manager: &ShaderManager) -> Result<Vec<String>, String> {
+        let prefab = self.prefabs.get(&instance.prefab).ok_or_else(|| format!("Prefab '{}' is not loaded", instance.prefab))?;
+        let is_taken = |name: &str| prefab.object_names(name).iter().any(|object_name| master_graphics_list.get_object(object_name).is_some());
+
+        let instance_name = match &instance.name {
+            Some(name) if is_taken(name) => return Err(format!("An object named '{}' already exists", name)),
+            Some(name) => name.clone(),
+            None => Self::unique_name(&instance.prefab, is_taken),
+        };
+
+        // Scene files get the same check in validation, building would leave the objects untextured
+        if let Some(texture_name) = &instance.texture_name {
+            if !texture_name.is_empty() && texture_manager.get_texture_id(texture_name).is_none() {
+                return Err(format!("Texture '{}' is not loaded", texture_name));
+            }
+        }
+
+        // Build the whole group before adding any of it, so a failure leaves nothing behind
+        let graphics_objects = prefab.instantiate(instance, &instance_name)?.into_iter()
+            .map(|obj_data| obj_data.build(texture_manager, shader_manager))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut names = Vec::new();
+        for graphics_object in graphics_objects {
+            let name = graphics_object.get_name().to_string();
+            master_graphics_list.add_object(Arc::new(RwLock::new(graphics_object)));
+            event_bus.publish(EngineEvent::ObjectSpawned(name.clone()));
+            names.push(name);
+        }
+        Ok(names)
+    }
+
+    /// Writes a scene's objects, in their current state, to a file that load_scene_from_json can read.
+    /// The shaders they use are written to the file's shaders section, and the scene's properties with them.
+    pub fn save_scene_to_json(scene: &Scene, shader_manager: &ShaderManager, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let objects: Vec<_> = scene.get_objects().iter().map(|obj| obj.read().unwrap().clone()).collect();
+        let properties = scene.get_properties().iter().map(|(key, value)| (key.clone(), value.clone())).collect();
+        Self::save_objects_to_json(objects, properties, shader_manager, file_path)
+    }
+
+    /// Writes every live object in the master graphics list to a scene file.
+    pub fn save_graphics_list_to_json(master_graphics_list: &MasterGraphicsList, shader_manager: &ShaderManager, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let objects: Vec<_> = master_graphics_list.get_objects().read().unwrap().values().map(|obj| obj.read().unwrap().clone()).collect();
+        Self::save_objects_to_json(objects, BTreeMap::new(), shader_manager, file_path)
+    }
+
+    // Prefab instances are saved as the plain objects they expanded into
+    fn save_objects_to_json(mut objects: Vec<Generic2DGraphicsObject>, properties: BTreeMap<String, serde_json::Value>, shader_manager: &ShaderManager, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        // Sort by name so saving the same objects always gives the same file
+        objects.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        let objects: Vec<ObjectData> = objects.iter().map(ObjectData::from_object).collect::<Result<_, _>>()?;
+
+        let mut shaders = BTreeMap::new();
+        for obj_data in &objects {
+            if !shaders.contains_key(&obj_data.shader) {
+                let definition = shader_manager.get_shader_definition(&obj_data.shader)
+                    .ok_or_else(|| format!("Shader '{}' used by object '{}' is not defined", obj_data.shader, obj_data.name))?;
+                shaders.insert(obj_data.shader.clone(), definition);
+            }
+        }
+
+        let scene_data = SceneData {
+            version: CURRENT_SCENE_VERSION,
+            shaders,
+            objects,
+            instances: Vec::new(),
+            properties,
+        };
+
+        fs::write(file_path, Self::scene_data_to_json(&scene_data)?)?;
+        Ok(())
+    }
+
+    // The text written to scene files
+    fn scene_data_to_json(scene_data: &SceneData) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(scene_data)
+    }
 }
 
-#[derive(Deserialize)]
-struct SceneData {
-    objects: Vec<ObjectData>,
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use serde_json::json;
+
+    use crate::engine::graphics::{shader_manager::ShaderDefinition, test_gl};
+
+    use super::*;
+
+    const SCENE_PATH: &str = "./src/resources/scenes/testscene2.json";
+
+    // The engine's shaders, textures and prefabs as loading testscene2 expects them, without a GL context
+    fn setup() -> (SceneManager, TextureManager, ShaderManager) {
+        let texture_manager = TextureManager::new();
+        for (index, (name, _)) in TextureManager::list_textures_in_directory("./src/resources/textures").unwrap().into_iter().enumerate() {
+            texture_manager.insert_texture_id(&name, index as u32 + 1);
+        }
+
+        let shader_manager = ShaderManager::new();
+        shader_manager.load_shaders_from_directory("./src/resources/shaders").unwrap();
+
+        let mut scene_manager = SceneManager::new();
+        scene_manager.load_prefabs_from_directory("./src/resources/prefabs", &texture_manager, &shader_manager).unwrap();
+
+        (scene_manager, texture_manager, shader_manager)
+    }
+
+    #[test]
+    fn scenes_survive_save_and_load() {
+        test_gl::load();
+        let (mut scene_manager, texture_manager, shader_manager) = setup();
+        let data = SceneManager::read_file(SCENE_PATH).unwrap();
+        let mut scene_data = scene_manager.parse_scene_data(SCENE_PATH, &data, &texture_manager, &shader_manager).unwrap();
+        assert_eq!(scene_data.version, CURRENT_SCENE_VERSION);
+        assert!(!scene_data.instances.is_empty());
+
+        // Use the optional fields too, so dropping any of them on save fails the test
+        let object = &mut scene_data.objects[0];
+        object.properties.insert("speed".to_string(), json!(0.25));
+        object.properties.insert("tags".to_string(), json!(["player", { "team": 2 }]));
+        object.draw_mode = Some("TriangleFan".to_string());
+        object.visible = Some(false);
+        scene_data.properties.insert("gravity".to_string(), json!(-9.8));
+
+        let source_path = std::env::temp_dir().join("rusted_open_save_source.json");
+        let source_path = source_path.to_str().unwrap();
+        fs::write(source_path, SceneManager::scene_data_to_json(&scene_data).unwrap()).unwrap();
+        scene_manager.load_scene_from_json(source_path, &texture_manager, &shader_manager).unwrap();
+        let source = scene_manager.get_scene("rusted_open_save_source").unwrap();
+
+        let copy_path = std::env::temp_dir().join("rusted_open_save_copy.json");
+        let copy_path = copy_path.to_str().unwrap();
+        SceneManager::save_scene_to_json(&source.read().unwrap(), &shader_manager, copy_path).unwrap();
+        scene_manager.load_scene_from_json(copy_path, &texture_manager, &shader_manager).unwrap();
+        let copy = scene_manager.get_scene("rusted_open_save_copy").unwrap();
+
+        let object_data = |scene: &Scene| {
+            let mut objects: Vec<ObjectData> = scene.get_objects().iter().map(|obj| ObjectData::from_object(&obj.read().unwrap()).unwrap()).collect();
+            objects.sort_by(|a, b| a.name.cmp(&b.name));
+            objects
+        };
+        let saved = object_data(&source.read().unwrap());
+        assert_eq!(object_data(&copy.read().unwrap()), saved);
+        assert_eq!(copy.read().unwrap().get_properties(), source.read().unwrap().get_properties());
+        assert_eq!(copy.read().unwrap().get_property("gravity"), Some(&json!(-9.8)));
+
+        let first = saved.iter().find(|obj| obj.name == scene_data.objects[0].name).unwrap();
+        assert_eq!(first, &scene_data.objects[0]);
+
+        // Saving what was loaded from a save gives the same file
+        let copy_again_path = std::env::temp_dir().join("rusted_open_save_copy_again.json");
+        let copy_again_path = copy_again_path.to_str().unwrap();
+        SceneManager::save_scene_to_json(&copy.read().unwrap(), &shader_manager, copy_again_path).unwrap();
+        assert_eq!(SceneManager::read_file(copy_again_path).unwrap(), SceneManager::read_file(copy_path).unwrap());
+    }
+
+    #[test]
+    fn spawning_rejects_bad_overrides() {
+        test_gl::load();
+        let (scene_manager, texture_manager, shader_manager) = setup();
+        let master_graphics_list = MasterGraphicsList::new();
+        let mut event_bus = EventBus::new();
+        let mut spawn = |instance: PrefabInstance| scene_manager.spawn_prefab(&instance, &master_graphics_list, &mut event_bus, &texture_manager, &shader_manager);
+
+        let mut instance = PrefabInstance::new("block").with_name("bad_position");
+        instance.position = Some(vec![1.0]);
+        assert!(spawn(instance).unwrap_err().contains("[x, y] or [x, y, z]"));
+
+        let error = spawn(PrefabInstance::new("block").with_name("bad_texture").with_texture("NotATexture")).unwrap_err();
+        assert!(error.contains("NotATexture"), "{}", error);
+        assert!(master_graphics_list.get_objects().read().unwrap().is_empty());
+
+        let mut instance = PrefabInstance::new("block").with_name("flat").with_texture("");
+        instance.position = Some(vec![0.5, -0.5]);
+        assert_eq!(spawn(instance).unwrap(), vec!["flat".to_string()]);
+        let flat = master_graphics_list.get_object("flat").unwrap();
+        assert_eq!(flat.read().unwrap().get_position(), Vector3::new(0.5, -0.5, 0.0));
+    }
+
+    fn scene_with_shader(shader_name: &str, fragment_shader: &str, texture_name: &str) -> String {
+        json!({
+            "version": CURRENT_SCENE_VERSION,
+            "shaders": { shader_name: { "vertex_shader": "void main() {}", "fragment_shader": fragment_shader } },
+            "objects": [{
+                "name": "quad",
+                "vertex_data": [0.0, 0.0, 1.0, 0.0, 1.0, 1.0],
+                "texture_coords": [0.0, 0.0, 1.0, 0.0, 1.0, 1.0],
+                "shader": shader_name,
+                "position": [0.0, 0.0, 0.0],
+                "rotation": 0.0,
+                "scale": 1.0,
+                "texture_name": texture_name,
+                "collision_modes": []
+            }]
+        }).to_string()
+    }
+
+    #[test]
+    fn scenes_cannot_redefine_library_shaders() {
+        let (mut scene_manager, texture_manager, shader_manager) = setup();
+        let library_textured = shader_manager.get_shader_definition("textured").unwrap();
+
+        let error = scene_manager.load_scene_from_str("clash.json", &scene_with_shader("textured", "void main() {}", ""), &texture_manager, &shader_manager).unwrap_err();
+        assert!(error.to_string().contains("$.shaders.textured"), "{}", error);
+        assert_eq!(shader_manager.get_shader_definition("textured"), Some(library_textured));
+        assert!(scene_manager.get_scene("clash").is_none());
+    }
+
+    #[test]
+    fn failed_scenes_leave_the_shader_library_alone() {
+        let (mut scene_manager, texture_manager, shader_manager) = setup();
+
+        let result = scene_manager.load_scene_from_str("broken.json", &scene_with_shader("glow", "void main() {}", "NotATexture"), &texture_manager, &shader_manager);
+        assert!(result.is_err());
+        assert!(!shader_manager.has_shader("glow"));
+    }
+
+    #[test]
+    fn reloading_a_scene_can_redefine_its_own_shaders() {
+        test_gl::load();
+        let (mut scene_manager, texture_manager, shader_manager) = setup();
+        let master_graphics_list = MasterGraphicsList::new();
+        let mut event_bus = EventBus::new();
+
+        let path = std::env::temp_dir().join("rusted_open_shader_reload.json");
+        let path = path.to_str().unwrap();
+        fs::write(path, scene_with_shader("glow", "void main() {}", "")).unwrap();
+        scene_manager.load_scene_from_json(path, &texture_manager, &shader_manager).unwrap();
+        scene_manager.push_scene("rusted_open_shader_reload", SceneTransition::Cut, &master_graphics_list, &mut event_bus).unwrap();
+        let old_program = master_graphics_list.get_object("quad").unwrap().read().unwrap().get_shader_program();
+
+        let edited = "void main() { gl_FragColor = vec4(1.0); }";
+        fs::write(path, scene_with_shader("glow", edited, "")).unwrap();
+        scene_manager.reload_scene("rusted_open_shader_reload", &master_graphics_list, &mut event_bus, &texture_manager, &shader_manager).unwrap();
+
+        let Some(ShaderDefinition::Inline { fragment_shader, .. }) = shader_manager.get_shader_definition("glow") else {
+            panic!("Shader 'glow' is not an inline shader");
+        };
+        assert_eq!(fragment_shader, edited);
+        assert_ne!(master_graphics_list.get_object("quad").unwrap().read().unwrap().get_shader_program(), old_program);
+
+        // Other scenes still cannot change it
+        let error = scene_manager.load_scene_from_str("other.json", &scene_with_shader("glow", "void main() {}", ""), &texture_manager, &shader_manager).unwrap_err();
+        assert!(error.to_string().contains("$.shaders.glow"), "{}", error);
+    }
+
+    fn tiled_map(data: [u32; 2], music: &str) -> String {
+        json!({
+            "orientation": "orthogonal", "width": 2, "height": 1, "tilewidth": 16, "tileheight": 16,
+            "properties": [{ "name": "music", "type": "string", "value": music }],
+            "tilesets": [{
+                "firstgid": 1, "name": "ground", "image": "ground.png",
+                "tilewidth": 16, "tileheight": 16, "imagewidth": 32, "imageheight": 16, "columns": 2, "tilecount": 2
+            }],
+            "layers": [{ "type": "tilelayer", "name": "ground", "width": 2, "height": 1, "data": data }]
+        }).to_string()
+    }
+
+    #[test]
+    fn tiled_maps_reload_and_keep_their_scene_name() {
+        test_gl::load();
+        let (mut scene_manager, texture_manager, shader_manager) = setup();
+        let master_graphics_list = MasterGraphicsList::new();
+        let mut event_bus = EventBus::new();
+
+        let dir = std::env::temp_dir().join("rusted_open_tiled_reload");
+        fs::create_dir_all(&dir).unwrap();
+        let map_path = dir.join("level1.tmj");
+        let map_path = map_path.to_str().unwrap();
+        fs::write(map_path, tiled_map([1, 0], "calm")).unwrap();
+        let texture_name = TiledMap::load(map_path).unwrap().tilesets[0].get_texture_name();
+        texture_manager.insert_texture_id(&texture_name, 100);
+
+        scene_manager.load_tiled_map(map_path, &TiledImportOptions::default(), &texture_manager, &shader_manager).unwrap();
+        scene_manager.push_scene("level1", SceneTransition::Cut, &master_graphics_list, &mut event_bus).unwrap();
+        assert!(scene_manager.is_loaded_from("level1", map_path));
+        let vertex_count = |scene_manager: &SceneManager| scene_manager.get_scene("level1").unwrap().read().unwrap().get_objects()[0].read().unwrap().get_vertex_data().len();
+        let one_tile = vertex_count(&scene_manager);
+
+        fs::write(map_path, tiled_map([1, 2], "tense")).unwrap();
+        scene_manager.reload_scene("level1", &master_graphics_list, &mut event_bus, &texture_manager, &shader_manager).unwrap();
+        assert_eq!(vertex_count(&scene_manager), one_tile * 2);
+        assert_eq!(scene_manager.get_scene("level1").unwrap().read().unwrap().get_property("music"), Some(&json!("tense")));
+
+        // A scene file with the same name does not replace the map
+        let scene_path = dir.join("level1.json");
+        let scene_path = scene_path.to_str().unwrap();
+        fs::write(scene_path, scene_with_shader("level1_shader", "void main() {}", "")).unwrap();
+        let error = scene_manager.load_scene_from_json(scene_path, &texture_manager, &shader_manager).unwrap_err();
+        assert!(error.to_string().contains("already loaded"), "{}", error);
+        assert!(scene_manager.is_loaded_from("level1", map_path));
+    }
+}
@@ -0,0 +1,119 @@
+use serde_json::{json, Map, Value};
+
+/// The scene format version written by this build of the engine.
+pub const CURRENT_SCENE_VERSION: u64 = 4;
+
+// Upgrades a document from the version at its index to the next version
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+const MIGRATIONS: [Migration; CURRENT_SCENE_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+];
+
+// Version 0 is the original format, which had no version field. Version 1 only adds the field.
+fn migrate_v0_to_v1(_scene: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+// Version 2 moves shader source out of each object into a shaders section that objects refer to by name.
+// Objects with identical source share one entry, named after a hash of the source so entries from
+// different upgraded scenes do not clash in the shader library.
+fn migrate_v1_to_v2(scene: &mut Map<String, Value>) -> Result<(), String> {
+    let mut shaders = Map::new();
+
+    if let Some(Value::Array(objects)) = scene.get_mut("objects") {
+        for object in objects.iter_mut().filter_map(Value::as_object_mut) {
+            // Leave objects without source alone, validation reports their missing shader
+            let (Some(Value::String(vertex_shader)), Some(Value::String(fragment_shader))) = (object.get("vertex_shader"), object.get("fragment_shader")) else {
+                continue;
+            };
+            let definition = json!({ "vertex_shader": vertex_shader, "fragment_shader": fragment_shader });
+
+            let name = format!("shader_{:016x}", source_hash(&[vertex_shader, fragment_shader]));
+            shaders.insert(name.clone(), definition);
+
+            object.remove("vertex_shader");
+            object.remove("fragment_shader");
+            object.insert("shader".to_string(), Value::String(name));
+        }
+    }
+
+    scene.insert("shaders".to_string(), Value::Object(shaders));
+    Ok(())
+}
+
+// Version 3 adds prefab instances and custom object properties, both optional
+fn migrate_v2_to_v3(_scene: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+// Version 4 adds an optional draw mode, so objects can be triangle meshes such as imported tilemaps,
+// and an optional visible flag for objects that only mark areas
+fn migrate_v3_to_v4(_scene: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+// FNV-1a, used over the std hasher since its output is not guaranteed to stay the same between releases
+fn source_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Reads a scene document's version, treating a missing version as 0.
+pub fn get_scene_version(document: &Value) -> Result<u64, String> {
+    match document.get("version") {
+        None => Ok(0),
+        Some(version) => version.as_u64().ok_or_else(|| format!("Scene version must be a whole number, found {}", version)),
+    }
+}
+
+/// Tells a scene document apart from other JSON files, such as the input map or combo configs.
+/// Scenes carry a version, or from before versioning, an objects list.
+pub fn is_scene_document(document: &Value) -> bool {
+    document.get("version").is_some_and(Value::is_u64) || document.get("objects").is_some_and(Value::is_array)
+}
+
+/// Upgrades a scene document to the current version one step at a time. Returns the version it started at.
+/// Documents from a newer engine are rejected rather than guessed at.
+pub fn migrate_scene(document: &mut Value) -> Result<u64, String> {
+    let version = get_scene_version(document)?;
+    if version > CURRENT_SCENE_VERSION {
+        return Err(format!("Scene version {} is newer than the newest supported version {}, update the engine to load it", version, CURRENT_SCENE_VERSION));
+    }
+
+    let scene = document.as_object_mut().ok_or("Scene must be a JSON object")?;
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(scene).map_err(|e| format!("Failed to upgrade scene from version {} to {}: {}", from_version, from_version + 1, e))?;
+        scene.insert("version".to_string(), Value::from(from_version as u64 + 1));
+    }
+
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_document(path: &str) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn only_scene_files_are_scene_documents() {
+        assert!(is_scene_document(&read_document("./src/resources/scenes/testscene2.json")));
+        assert!(is_scene_document(&serde_json::json!({ "objects": [] })));
+
+        assert!(!is_scene_document(&read_document("./src/resources/config/input_map.json")));
+        assert!(!is_scene_document(&read_document("./src/resources/config/combos.json")));
+        assert!(!is_scene_document(&read_document("./src/resources/config/window.json")));
+    }
+}
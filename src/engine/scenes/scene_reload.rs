@@ -0,0 +1,143 @@
+use std::{collections::BTreeSet, fs, time::SystemTime};
+
+use crate::engine::graphics::internal_object::graphics_object::Generic2DGraphicsObject;
+
+use super::{scene_data::ObjectData, tiled_import::TiledImportOptions};
+
+/// A scene loaded from a file, kept so the file can be watched and reloaded.
+pub struct SceneFile {
+    path: String,
+    modified: Option<SystemTime>,
+    objects: Vec<ObjectData>, // As last loaded, with prefab instances expanded
+    tiled_options: Option<TiledImportOptions>, // Set for Tiled maps, which are imported again instead of parsed
+}
+
+impl SceneFile {
+    pub fn new(path: &str, objects: Vec<ObjectData>) -> Self {
+        Self {
+            path: path.to_string(),
+            modified: Self::read_modified(path),
+            objects,
+            tiled_options: None,
+        }
+    }
+
+    /// Marks the file as a Tiled map, imported with these options.
+    pub fn with_tiled_options(mut self, options: TiledImportOptions) -> Self {
+        self.tiled_options = Some(options);
+        self
+    }
+
+    pub fn get_tiled_options(&self) -> Option<&TiledImportOptions> {
+        self.tiled_options.as_ref()
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get_objects(&self) -> &[ObjectData] {
+        &self.objects
+    }
+
+    pub fn set_objects(&mut self, objects: Vec<ObjectData>) {
+        self.objects = objects;
+    }
+
+    /// Checks the file's modified time, returning true once per change.
+    pub fn check_changed(&mut self) -> bool {
+        let modified = Self::read_modified(&self.path);
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            return true;
+        }
+        false
+    }
+
+    fn read_modified(path: &str) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+/// How often watched scene files are checked for changes. Off until an interval is set.
+pub struct HotReload {
+    interval: Option<f32>, // Seconds between checks
+    elapsed: f32,
+}
+
+impl Default for HotReload {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HotReload {
+    pub fn new() -> Self {
+        Self {
+            interval: None,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: Option<f32>) {
+        self.interval = interval;
+        self.elapsed = 0.0;
+    }
+
+    pub fn get_interval(&self) -> Option<f32> {
+        self.interval
+    }
+
+    /// Advances the timer, returning true when the files should be checked.
+    pub fn is_due(&mut self, delta_time: f32) -> bool {
+        let Some(interval) = self.interval else {
+            return false;
+        };
+        self.elapsed += delta_time;
+        if self.elapsed >= interval {
+            self.elapsed = 0.0;
+            return true;
+        }
+        false
+    }
+}
+
+/// Brings a live object up to date with its edited data. Only what changed between the old and new data
+/// is applied, so anything changed at runtime that the edit did not touch is kept.
+/// The rebuilt object supplies the new geometry, texture and shader. A shader whose definition was edited
+/// counts as changed even though the object still names the same one.
+pub fn apply_object_changes(obj: &mut Generic2DGraphicsObject, old: &ObjectData, new: &ObjectData, rebuilt: &Generic2DGraphicsObject, shader_redefined: bool) {
+    if shader_redefined || old.vertex_data != new.vertex_data || old.texture_coords != new.texture_coords || old.shader != new.shader || old.texture_name != new.texture_name || old.draw_mode != new.draw_mode {
+        obj.copy_render_data_from(rebuilt);
+    }
+
+    if old.position != new.position {
+        obj.set_position(rebuilt.get_position());
+    }
+    if old.rotation != new.rotation {
+        obj.set_rotation(rebuilt.get_rotation());
+    }
+    if old.scale != new.scale {
+        obj.set_scale(rebuilt.get_scale());
+    }
+
+    if old.visible != new.visible {
+        obj.set_visible(rebuilt.is_visible());
+    }
+
+    let old_modes: BTreeSet<&String> = old.collision_modes.iter().collect();
+    let new_modes: BTreeSet<&String> = new.collision_modes.iter().collect();
+    if old_modes != new_modes {
+        obj.set_collision_modes(rebuilt.get_collision_modes().clone());
+    }
+
+    for key in old.properties.keys().chain(new.properties.keys()) {
+        match (old.properties.get(key), new.properties.get(key)) {
+            (old_value, Some(new_value)) if old_value != Some(new_value) => obj.set_property(key, new_value.clone()),
+            (Some(_), None) => {
+                obj.remove_property(key);
+            },
+            _ => {},
+        }
+    }
+}
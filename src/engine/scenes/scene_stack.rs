@@ -0,0 +1,299 @@
+use std::{collections::HashMap, sync::{Arc, RwLock}};
+
+use crate::engine::{events::event_bus::{EngineEvent, EventBus}, graphics::util::master_graphics_list::{MasterGraphicsList, NameCollision}};
+
+use super::scene::Scene;
+
+/// How the objects of a scene change over when the stack changes. Durations are in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneTransition {
+    Cut,
+    Fade { duration: f32 }, // Fade the old scene out, then the new one in
+    Crossfade { duration: f32 }, // Fade the old scene out while the new one fades in
+}
+
+/// Called with the scene name and the master graphics list.
+pub type SceneHook = Box<dyn FnMut(&str, &MasterGraphicsList)>;
+
+/// Callbacks for when a scene's objects enter or leave the world.
+#[derive(Default)]
+pub struct SceneHooks {
+    on_enter: Option<SceneHook>,
+    on_exit: Option<SceneHook>,
+}
+
+impl SceneHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs once the scene's objects are in the master graphics list.
+    pub fn on_enter<F: FnMut(&str, &MasterGraphicsList) + 'static>(mut self, hook: F) -> Self {
+        self.on_enter = Some(Box::new(hook));
+        self
+    }
+
+    /// Runs when the scene leaves the stack, before its objects are removed.
+    pub fn on_exit<F: FnMut(&str, &MasterGraphicsList) + 'static>(mut self, hook: F) -> Self {
+        self.on_exit = Some(Box::new(hook));
+        self
+    }
+}
+
+// A scene on the stack and the objects it put into the master graphics list
+struct StackEntry {
+    name: String,
+    objects: Vec<String>,
+}
+
+// A scene waiting to be added once a fade has faded out the old one
+struct PendingScene {
+    name: String,
+    scene: Arc<RwLock<Scene>>,
+}
+
+struct ActiveTransition {
+    transition: SceneTransition,
+    elapsed: f32,
+    outgoing: Vec<String>, // Objects fading out, removed when they reach zero
+    incoming: Vec<String>, // Objects fading in
+    pending: Option<PendingScene>,
+}
+
+/// Scenes currently in the world, bottom to top. Scenes below the top stay in the world,
+/// so a pause menu can be pushed over gameplay and popped to return to it.
+pub struct SceneStack {
+    entries: Vec<StackEntry>,
+    hooks: HashMap<String, SceneHooks>,
+    transition: Option<ActiveTransition>,
+    name_collision: NameCollision, // How objects are named when they clash with objects already in the world
+}
+
+impl Default for SceneStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneStack {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            hooks: HashMap::new(),
+            transition: None,
+            name_collision: NameCollision::Namespace,
+        }
+    }
+
+    pub fn set_name_collision(&mut self, name_collision: NameCollision) {
+        self.name_collision = name_collision;
+    }
+
+    pub fn get_name_collision(&self) -> NameCollision {
+        self.name_collision
+    }
+
+    pub fn set_hooks(&mut self, scene_name: &str, hooks: SceneHooks) {
+        self.hooks.insert(scene_name.to_string(), hooks);
+    }
+
+    /// Scene names from the bottom of the stack to the top.
+    pub fn get_scene_names(&self) -> Vec<String> {
+        self.entries.iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    pub fn get_top_scene(&self) -> Option<&str> {
+        self.entries.last().map(|entry| entry.name.as_str())
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    pub fn contains(&self, scene_name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.name == scene_name)
+    }
+
+    /// Counts an object added after a scene was pushed as part of the topmost copy of that scene,
+    /// so it is removed when the scene leaves the stack.
+    pub fn track_object(&mut self, scene_name: &str, object_name: &str) {
+        if let Some(entry) = self.entries.iter_mut().rev().find(|entry| entry.name == scene_name) {
+            entry.objects.push(object_name.to_string());
+        }
+    }
+
+    /// Stops counting an object that was removed from the world as part of its scene.
+    pub fn untrack_object(&mut self, object_name: &str) {
+        for entry in &mut self.entries {
+            entry.objects.retain(|name| name != object_name);
+        }
+    }
+
+    /// Puts a scene on top of the stack. The scenes below stay in the world.
+    pub fn push(&mut self, name: &str, scene: Arc<RwLock<Scene>>, transition: SceneTransition, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        self.change(Vec::new(), PendingScene { name: name.to_string(), scene }, transition, master_graphics_list, event_bus);
+    }
+
+    /// Takes the top scene off the stack and returns its name.
+    pub fn pop(&mut self, transition: SceneTransition, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> Option<String> {
+        self.finish_transition(master_graphics_list, event_bus);
+        let entry = self.exit_top(master_graphics_list, event_bus)?;
+        self.start_transition(transition, entry.objects, None, master_graphics_list, event_bus);
+        Some(entry.name)
+    }
+
+    /// Swaps the top scene for another, such as moving to the next level.
+    pub fn replace(&mut self, name: &str, scene: Arc<RwLock<Scene>>, transition: SceneTransition, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        self.finish_transition(master_graphics_list, event_bus);
+        let outgoing = self.exit_top(master_graphics_list, event_bus).map(|entry| entry.objects).unwrap_or_default();
+        self.change(outgoing, PendingScene { name: name.to_string(), scene }, transition, master_graphics_list, event_bus);
+    }
+
+    /// Removes every scene immediately, top first.
+    pub fn clear(&mut self, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        self.finish_transition(master_graphics_list, event_bus);
+        while let Some(entry) = self.exit_top(master_graphics_list, event_bus) {
+            Self::remove_objects(&entry.objects, master_graphics_list, event_bus);
+        }
+    }
+
+    /// Takes every copy of a scene off the stack wherever it is, running its exit hook.
+    /// Its objects are left for the caller to remove. Returns whether the scene was on the stack.
+    pub fn remove(&mut self, name: &str, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> bool {
+        self.finish_transition(master_graphics_list, event_bus);
+
+        let count = self.entries.len();
+        self.entries.retain(|entry| entry.name != name);
+        let removed = count - self.entries.len();
+
+        for _ in 0..removed {
+            if let Some(hook) = self.hooks.get_mut(name).and_then(|hooks| hooks.on_exit.as_mut()) {
+                hook(name, master_graphics_list);
+            }
+            event_bus.publish(EngineEvent::SceneUnloaded(name.to_string()));
+        }
+        removed > 0
+    }
+
+    fn change(&mut self, outgoing: Vec<String>, incoming: PendingScene, transition: SceneTransition, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        self.finish_transition(master_graphics_list, event_bus);
+        self.start_transition(transition, outgoing, Some(incoming), master_graphics_list, event_bus);
+    }
+
+    fn start_transition(&mut self, transition: SceneTransition, outgoing: Vec<String>, incoming: Option<PendingScene>, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        let mut active = ActiveTransition {
+            transition,
+            elapsed: 0.0,
+            outgoing,
+            incoming: Vec::new(),
+            pending: incoming,
+        };
+
+        // Everything but a fade brings the new scene in straight away, a fade waits until the old scene is gone
+        let fading_out_first = matches!(transition, SceneTransition::Fade { .. }) && !active.outgoing.is_empty();
+        if !fading_out_first {
+            if let Some(pending) = active.pending.take() {
+                active.incoming = self.enter(pending, master_graphics_list, event_bus);
+            }
+        }
+
+        self.transition = Some(active);
+        self.update(0.0, master_graphics_list, event_bus);
+    }
+
+    /// Advances the running transition, called once per frame.
+    pub fn update(&mut self, delta_time: f32, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        let Some(mut active) = self.transition.take() else {
+            return;
+        };
+        active.elapsed += delta_time;
+
+        let (fade_out, fade_in, finished) = match active.transition {
+            SceneTransition::Cut => (0.0, 1.0, true),
+            SceneTransition::Crossfade { duration } => {
+                let progress = Self::progress(active.elapsed, duration);
+                (1.0 - progress, progress, progress >= 1.0)
+            },
+            SceneTransition::Fade { duration } => {
+                // Out over the first half, in over the second, or in over the whole time if nothing is fading out
+                if active.pending.is_some() {
+                    let progress = Self::progress(active.elapsed, duration / 2.0);
+                    if progress >= 1.0 {
+                        Self::remove_objects(&std::mem::take(&mut active.outgoing), master_graphics_list, event_bus);
+                        let pending = active.pending.take().unwrap();
+                        active.incoming = self.enter(pending, master_graphics_list, event_bus);
+                        active.elapsed = 0.0;
+                        active.transition = SceneTransition::Fade { duration: duration / 2.0 };
+                        (0.0, 0.0, false)
+                    } else {
+                        (1.0 - progress, 0.0, false)
+                    }
+                } else {
+                    let progress = Self::progress(active.elapsed, duration);
+                    (1.0 - progress, progress, progress >= 1.0)
+                }
+            },
+        };
+
+        Self::set_opacity(&active.outgoing, fade_out, master_graphics_list);
+        Self::set_opacity(&active.incoming, fade_in, master_graphics_list);
+
+        if finished {
+            Self::remove_objects(&active.outgoing, master_graphics_list, event_bus);
+        } else {
+            self.transition = Some(active);
+        }
+    }
+
+    // Jump the running transition to its end
+    fn finish_transition(&mut self, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        while self.transition.is_some() {
+            self.update(f32::INFINITY, master_graphics_list, event_bus);
+        }
+    }
+
+    fn progress(elapsed: f32, duration: f32) -> f32 {
+        if duration <= 0.0 { 1.0 } else { (elapsed / duration).min(1.0) }
+    }
+
+    // Add a scene's objects to the world, push it and run its enter hook
+    fn enter(&mut self, pending: PendingScene, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> Vec<String> {
+        let scene = pending.scene.read().unwrap();
+        let objects = master_graphics_list.load_scene_additive(&pending.name, &scene, self.name_collision);
+        for name in &objects {
+            event_bus.publish(EngineEvent::ObjectSpawned(name.clone()));
+        }
+        self.entries.push(StackEntry { name: pending.name.clone(), objects: objects.clone() });
+        event_bus.publish(EngineEvent::SceneLoaded(pending.name.clone()));
+
+        if let Some(hook) = self.hooks.get_mut(&pending.name).and_then(|hooks| hooks.on_enter.as_mut()) {
+            hook(&pending.name, master_graphics_list);
+        }
+        objects
+    }
+
+    // Pop the top scene and run its exit hook, leaving its objects for the caller to remove
+    fn exit_top(&mut self, master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) -> Option<StackEntry> {
+        let entry = self.entries.pop()?;
+        if let Some(hook) = self.hooks.get_mut(&entry.name).and_then(|hooks| hooks.on_exit.as_mut()) {
+            hook(&entry.name, master_graphics_list);
+        }
+        event_bus.publish(EngineEvent::SceneUnloaded(entry.name.clone()));
+        Some(entry)
+    }
+
+    fn set_opacity(objects: &[String], opacity: f32, master_graphics_list: &MasterGraphicsList) {
+        for name in objects {
+            if let Some(obj) = master_graphics_list.get_object(name) {
+                obj.write().unwrap().set_opacity(opacity);
+            }
+        }
+    }
+
+    fn remove_objects(objects: &[String], master_graphics_list: &MasterGraphicsList, event_bus: &mut EventBus) {
+        for name in objects {
+            master_graphics_list.remove_object(name);
+            event_bus.publish(EngineEvent::ObjectDespawned(name.clone()));
+        }
+    }
+}
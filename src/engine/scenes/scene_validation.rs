@@ -0,0 +1,479 @@
+use std::{collections::{HashMap, HashSet}, error::Error, fmt, path::Path};
+
+use serde_json::{Map, Value};
+
+use crate::engine::graphics::{internal_object::graphics_object::{CollisionMode, DrawMode}, shader_manager::{ShaderDefinition, ShaderManager}, texture_manager::TextureManager};
+
+use super::{prefab::Prefab, scene_migration};
+
+/// One problem found in a scene file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneValidationError {
+    pub file: String,
+    pub json_path: String, // Such as $.objects[2].position
+    pub line: usize,
+    pub column: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for SceneValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}: {}", self.file, self.line, self.column, self.json_path, self.reason)
+    }
+}
+
+impl Error for SceneValidationError {}
+
+/// Every problem found in a scene file, in document order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneValidationErrors {
+    errors: Vec<SceneValidationError>,
+}
+
+impl SceneValidationErrors {
+    pub fn get_errors(&self) -> &[SceneValidationError] {
+        &self.errors
+    }
+}
+
+impl fmt::Display for SceneValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} problem(s) in scene", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "\n  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for SceneValidationErrors {}
+
+/// Parses scene JSON, upgrades it to the current version and checks it against the scene format,
+/// collecting every problem rather than stopping at the first.
+/// Textures named by objects must already be loaded, an empty texture name means no texture.
+/// Shaders must be defined in the scene's shaders section or already be in the shader library,
+/// and instanced prefabs must already be loaded.
+pub fn validate_scene_json(file: &str, data: &str, texture_manager: &TextureManager, shader_manager: &ShaderManager, prefabs: &HashMap<String, Prefab>) -> Result<Value, SceneValidationErrors> {
+    let mut document: Value = serde_json::from_str(data).map_err(|e| SceneValidationErrors {
+        errors: vec![SceneValidationError {
+            file: file.to_string(),
+            json_path: "$".to_string(),
+            line: e.line(),
+            column: e.column(),
+            reason: e.to_string(),
+        }],
+    })?;
+
+    let mut validator = Validator {
+        file,
+        data,
+        offsets: index_offsets(data),
+        errors: Vec::new(),
+    };
+
+    // Older documents are checked against the current format once upgraded
+    if let Err(reason) = scene_migration::migrate_scene(&mut document) {
+        validator.error("$.version", reason);
+        return Err(SceneValidationErrors { errors: validator.errors });
+    }
+    validator.validate_scene(&document, texture_manager, shader_manager, prefabs);
+
+    if validator.errors.is_empty() {
+        Ok(document)
+    } else {
+        validator.errors.sort_by_key(|error| (error.line, error.column));
+        Err(SceneValidationErrors { errors: validator.errors })
+    }
+}
+
+struct Validator<'a> {
+    file: &'a str,
+    data: &'a str,
+    offsets: HashMap<String, usize>, // Byte offset of each value by JSON path
+    errors: Vec<SceneValidationError>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, json_path: &str, reason: String) {
+        // Fall back to the nearest parent that exists, for missing fields
+        let mut located = json_path;
+        let offset = loop {
+            if let Some(offset) = self.offsets.get(located) {
+                break *offset;
+            }
+            match located.rfind(['.', '[']) {
+                Some(index) => located = &located[..index],
+                None => break 0,
+            }
+        };
+        let (line, column) = line_column(self.data, offset);
+
+        self.errors.push(SceneValidationError {
+            file: self.file.to_string(),
+            json_path: json_path.to_string(),
+            line,
+            column,
+            reason,
+        });
+    }
+
+    fn validate_scene(&mut self, document: &Value, texture_manager: &TextureManager, shader_manager: &ShaderManager, prefabs: &HashMap<String, Prefab>) {
+        let Some(root) = document.as_object() else {
+            self.error("$", "Scene must be a JSON object".to_string());
+            return;
+        };
+        let Some(objects) = root.get("objects") else {
+            self.error("$", "Missing field 'objects'".to_string());
+            return;
+        };
+        let Some(objects) = objects.as_array() else {
+            self.error("$.objects", "Expected an array of objects".to_string());
+            return;
+        };
+
+        let scene_shaders = self.validate_shaders(root, shader_manager);
+
+        let mut names = HashSet::new();
+        for (index, object) in objects.iter().enumerate() {
+            let path = format!("$.objects[{}]", index);
+            let Some(object) = object.as_object() else {
+                self.error(&path, "Expected an object".to_string());
+                continue;
+            };
+
+            if let Some(name) = self.string_field(object, &path, "name") {
+                if !names.insert(name.to_string()) {
+                    self.error(&format!("{}.name", path), format!("Duplicate object name '{}'", name));
+                }
+            }
+            if let Some(shader) = self.string_field(object, &path, "shader") {
+                if !scene_shaders.contains(shader) && !shader_manager.has_shader(shader) {
+                    self.error(&format!("{}.shader", path), format!("Shader '{}' is not defined in the scene or the shader library", shader));
+                }
+            }
+            self.number_field(object, &path, "rotation");
+            self.number_field(object, &path, "scale");
+
+            let draw_mode = self.validate_draw_mode(object, &path);
+            let vertex_count = self.number_array_field(object, &path, "vertex_data");
+            if let Some(len) = vertex_count {
+                if len % 2 != 0 || len < 6 {
+                    self.error(&format!("{}.vertex_data", path), format!("Expected x, y pairs for at least 3 vertices, found {} values", len));
+                } else if draw_mode == Some(DrawMode::Triangles) && len % 6 != 0 {
+                    self.error(&format!("{}.vertex_data", path), format!("Expected 3 vertices per triangle, found {} vertices", len / 2));
+                }
+            }
+            if let Some(len) = self.number_array_field(object, &path, "texture_coords") {
+                if vertex_count.is_some_and(|vertex_count| vertex_count != len) {
+                    self.error(&format!("{}.texture_coords", path), format!("Expected {} values to match vertex_data, found {}", vertex_count.unwrap_or_default(), len));
+                }
+            }
+            if let Some(len) = self.number_array_field(object, &path, "position") {
+                if len != 3 {
+                    self.error(&format!("{}.position", path), format!("Expected [x, y, z], found {} values", len));
+                }
+            }
+
+            if let Some(texture_name) = self.string_field(object, &path, "texture_name") {
+                self.check_texture(texture_manager, &path, texture_name);
+            }
+
+            if object.get("visible").is_some_and(|visible| !visible.is_boolean()) {
+                self.error(&format!("{}.visible", path), "Expected true or false".to_string());
+            }
+
+            self.validate_collision_modes(object, &path);
+            self.validate_properties(object, &path);
+        }
+
+        self.validate_instances(root, texture_manager, prefabs, &mut names);
+        self.validate_properties(root, "$");
+    }
+
+    fn check_texture(&mut self, texture_manager: &TextureManager, path: &str, texture_name: &str) {
+        if !texture_name.is_empty() && texture_manager.get_texture_id(texture_name).is_none() {
+            self.error(&format!("{}.texture_name", path), format!("Texture '{}' is not loaded", texture_name));
+        }
+    }
+
+    fn validate_properties(&mut self, object: &Map<String, Value>, path: &str) {
+        if object.get("properties").is_some_and(|properties| !properties.is_object()) {
+            self.error(&format!("{}.properties", path), "Expected an object of custom properties".to_string());
+        }
+    }
+
+    // Check prefab instances, where everything but the prefab name is optional
+    fn validate_instances(&mut self, root: &Map<String, Value>, texture_manager: &TextureManager, prefabs: &HashMap<String, Prefab>, names: &mut HashSet<String>) {
+        let Some(instances) = root.get("instances") else {
+            return;
+        };
+        let Some(instances) = instances.as_array() else {
+            self.error("$.instances", "Expected an array of prefab instances".to_string());
+            return;
+        };
+
+        for (index, instance) in instances.iter().enumerate() {
+            let path = format!("$.instances[{}]", index);
+            let Some(instance) = instance.as_object() else {
+                self.error(&path, "Expected an object".to_string());
+                continue;
+            };
+
+            if let Some(prefab) = self.string_field(instance, &path, "prefab") {
+                if !prefabs.contains_key(prefab) {
+                    self.error(&format!("{}.prefab", path), format!("Prefab '{}' is not loaded", prefab));
+                }
+            }
+            if instance.contains_key("name") {
+                if let Some(name) = self.string_field(instance, &path, "name") {
+                    if !names.insert(name.to_string()) {
+                        self.error(&format!("{}.name", path), format!("Duplicate object name '{}'", name));
+                    }
+                }
+            }
+            if instance.contains_key("position") {
+                if let Some(len) = self.number_array_field(instance, &path, "position") {
+                    if len != 3 {
+                        self.error(&format!("{}.position", path), format!("Expected [x, y, z], found {} values", len));
+                    }
+                }
+            }
+            for field in ["rotation", "scale"] {
+                if instance.contains_key(field) {
+                    self.number_field(instance, &path, field);
+                }
+            }
+            if instance.contains_key("texture_name") {
+                if let Some(texture_name) = self.string_field(instance, &path, "texture_name") {
+                    self.check_texture(texture_manager, &path, texture_name);
+                }
+            }
+            self.validate_properties(instance, &path);
+        }
+    }
+
+    // Check the shaders section and return the names it defines
+    fn validate_shaders(&mut self, root: &Map<String, Value>, shader_manager: &ShaderManager) -> HashSet<String> {
+        let mut names = HashSet::new();
+        let Some(shaders) = root.get("shaders") else {
+            return names;
+        };
+        let Some(shaders) = shaders.as_object() else {
+            self.error("$.shaders", "Expected an object of shaders by name".to_string());
+            return names;
+        };
+
+        for (name, shader) in shaders {
+            let path = format!("$.shaders.{}", name);
+            let Some(shader) = shader.as_object() else {
+                self.error(&path, "Expected a shader definition".to_string());
+                continue;
+            };
+
+            if shader.contains_key("vertex_shader") || shader.contains_key("fragment_shader") {
+                self.string_field(shader, &path, "vertex_shader");
+                self.string_field(shader, &path, "fragment_shader");
+            } else if shader.contains_key("vertex_path") || shader.contains_key("fragment_path") {
+                for field in ["vertex_path", "fragment_path"] {
+                    if let Some(file_path) = self.string_field(shader, &path, field) {
+                        if !Path::new(file_path).is_file() {
+                            self.error(&format!("{}.{}", path, field), format!("Shader file '{}' does not exist", file_path));
+                        }
+                    }
+                }
+            } else {
+                self.error(&path, "Expected vertex_shader and fragment_shader source, or vertex_path and fragment_path files".to_string());
+            }
+
+            // A scene may repeat a library shader but only redefine its own, other scenes' objects rely on the rest
+            if let Ok(definition) = serde_json::from_value::<ShaderDefinition>(Value::Object(shader.clone())) {
+                if let Err(reason) = shader_manager.check_definition(self.file, name, &definition) {
+                    self.error(&path, format!("{}, give this one another name", reason));
+                }
+            }
+            names.insert(name.clone());
+        }
+
+        names
+    }
+
+    // Returns the draw mode if it is valid, TriangleFan if none is given
+    fn validate_draw_mode(&mut self, object: &Map<String, Value>, path: &str) -> Option<DrawMode> {
+        match object.get("draw_mode") {
+            None => Some(DrawMode::TriangleFan),
+            Some(Value::String(name)) => {
+                let draw_mode = DrawMode::from_name(name);
+                if draw_mode.is_none() {
+                    self.error(&format!("{}.draw_mode", path), format!("Unknown draw mode '{}', expected TriangleFan or Triangles", name));
+                }
+                draw_mode
+            },
+            Some(_) => {
+                self.error(&format!("{}.draw_mode", path), "Expected a draw mode name".to_string());
+                None
+            },
+        }
+    }
+
+    fn validate_collision_modes(&mut self, object: &Map<String, Value>, path: &str) {
+        let path = format!("{}.collision_modes", path);
+        let Some(modes) = object.get("collision_modes") else {
+            self.error(&path, "Missing field 'collision_modes'".to_string());
+            return;
+        };
+        let Some(modes) = modes.as_array() else {
+            self.error(&path, "Expected an array of collision mode names".to_string());
+            return;
+        };
+
+        for (index, mode) in modes.iter().enumerate() {
+            let mode_path = format!("{}[{}]", path, index);
+            match mode.as_str() {
+                Some(name) if CollisionMode::from_name(name).is_none() => {
+                    self.error(&mode_path, format!("Unknown collision mode '{}', expected AABB, Circle or OBB", name));
+                },
+                Some(_) => {},
+                None => self.error(&mode_path, "Expected a collision mode name".to_string()),
+            }
+        }
+    }
+
+    fn string_field<'v>(&mut self, object: &'v Map<String, Value>, path: &str, field: &str) -> Option<&'v str> {
+        match object.get(field) {
+            Some(Value::String(value)) => Some(value),
+            Some(_) => {
+                self.error(&format!("{}.{}", path, field), "Expected a string".to_string());
+                None
+            },
+            None => {
+                self.error(&format!("{}.{}", path, field), format!("Missing field '{}'", field));
+                None
+            },
+        }
+    }
+
+    fn number_field(&mut self, object: &Map<String, Value>, path: &str, field: &str) {
+        match object.get(field) {
+            Some(Value::Number(_)) => {},
+            Some(_) => self.error(&format!("{}.{}", path, field), "Expected a number".to_string()),
+            None => self.error(&format!("{}.{}", path, field), format!("Missing field '{}'", field)),
+        }
+    }
+
+    // Returns the array length if the field is an array of numbers
+    fn number_array_field(&mut self, object: &Map<String, Value>, path: &str, field: &str) -> Option<usize> {
+        let field_path = format!("{}.{}", path, field);
+        let values = match object.get(field) {
+            Some(Value::Array(values)) => values,
+            Some(_) => {
+                self.error(&field_path, "Expected an array of numbers".to_string());
+                return None;
+            },
+            None => {
+                self.error(&field_path, format!("Missing field '{}'", field));
+                return None;
+            },
+        };
+
+        let mut valid = true;
+        for (index, value) in values.iter().enumerate() {
+            if !value.is_number() {
+                self.error(&format!("{}[{}]", field_path, index), "Expected a number".to_string());
+                valid = false;
+            }
+        }
+        valid.then_some(values.len())
+    }
+}
+
+// 1-based line and column of a byte offset
+fn line_column(data: &str, offset: usize) -> (usize, usize) {
+    let before = &data[..offset.min(data.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |text| text.chars().count()) + 1;
+    (line, column)
+}
+
+// Record where every value starts, keyed by JSON path. The text has already parsed, so this does not re-check syntax.
+fn index_offsets(data: &str) -> HashMap<String, usize> {
+    let mut offsets = HashMap::new();
+    let mut position = 0;
+    index_value(data.as_bytes(), &mut position, "$".to_string(), &mut offsets);
+    offsets
+}
+
+fn skip_whitespace(bytes: &[u8], position: &mut usize) {
+    while *position < bytes.len() && bytes[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+}
+
+// Skip a string starting at its opening quote, returning its raw contents
+fn read_string(bytes: &[u8], position: &mut usize) -> String {
+    let start = *position + 1;
+    *position = start;
+    while *position < bytes.len() && bytes[*position] != b'"' {
+        if bytes[*position] == b'\\' {
+            *position += 1;
+        }
+        *position += 1;
+    }
+    let contents = String::from_utf8_lossy(&bytes[start..(*position).min(bytes.len())]).into_owned();
+    *position += 1;
+    contents
+}
+
+fn index_value(bytes: &[u8], position: &mut usize, path: String, offsets: &mut HashMap<String, usize>) {
+    skip_whitespace(bytes, position);
+    if *position >= bytes.len() {
+        return;
+    }
+    offsets.insert(path.clone(), *position);
+
+    match bytes[*position] {
+        b'{' => {
+            *position += 1;
+            loop {
+                skip_whitespace(bytes, position);
+                if *position >= bytes.len() || bytes[*position] == b'}' {
+                    break;
+                }
+                let key = read_string(bytes, position);
+                skip_whitespace(bytes, position);
+                *position += 1; // The colon
+                index_value(bytes, position, format!("{}.{}", path, key), offsets);
+                skip_whitespace(bytes, position);
+                if *position < bytes.len() && bytes[*position] == b',' {
+                    *position += 1;
+                }
+            }
+            *position += 1;
+        },
+        b'[' => {
+            *position += 1;
+            let mut index = 0;
+            loop {
+                skip_whitespace(bytes, position);
+                if *position >= bytes.len() || bytes[*position] == b']' {
+                    break;
+                }
+                index_value(bytes, position, format!("{}[{}]", path, index), offsets);
+                index += 1;
+                skip_whitespace(bytes, position);
+                if *position < bytes.len() && bytes[*position] == b',' {
+                    *position += 1;
+                }
+            }
+            *position += 1;
+        },
+        b'"' => {
+            read_string(bytes, position);
+        },
+        _ => {
+            // Numbers, booleans and null run until the next delimiter
+            while *position < bytes.len() && !matches!(bytes[*position], b',' | b']' | b'}') && !bytes[*position].is_ascii_whitespace() {
+                *position += 1;
+            }
+        },
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::engine::graphics::texture_manager::TextureManager;
+
+use super::{scene_data::ObjectData, tiled_map::{self, TileLayer, TiledLayer, TiledMap, TiledObject, TiledTileset, FLIPPED_DIAGONALLY, FLIPPED_HORIZONTALLY, FLIPPED_VERTICALLY, GID_MASK}};
+
+const ELLIPSE_SEGMENTS: usize = 16;
+
+/// Property of a tile layer object holding the custom properties of its tiles, keyed by "column,row".
+pub const TILE_PROPERTIES_KEY: &str = "tile_properties";
+
+/// Settings for turning a Tiled map into scene objects.
+#[derive(Debug, Clone)]
+pub struct TiledImportOptions {
+    pub pixels_per_unit: f32, // Map pixels per world unit, the map's top left corner is placed at the origin
+    pub shader: String, // Shader library name used for every object
+}
+
+impl Default for TiledImportOptions {
+    fn default() -> Self {
+        Self {
+            pixels_per_unit: 320.0,
+            shader: "textured".to_string(),
+        }
+    }
+}
+
+/// A map converted to the scene format.
+pub struct TiledImport {
+    pub objects: Vec<ObjectData>,
+    pub properties: BTreeMap<String, Value>, // The map's own custom properties
+}
+
+/// Converts a map into scene objects, loading tileset images through the texture manager.
+/// Each tile layer becomes one triangle mesh per tileset it uses, with the custom properties of its tiles
+/// kept per cell under TILE_PROPERTIES_KEY. Each object in an object layer becomes
+/// one object, with collision modes from its shape: AABB for rectangles, polygons and tiles, Circle for ellipses.
+/// Shapes other than tiles are hidden, as are objects in hidden layers. Text objects are skipped.
+pub fn import_tiled_map(map: &TiledMap, options: &TiledImportOptions, texture_manager: &TextureManager) -> Result<TiledImport, String> {
+    for tileset in &map.tilesets {
+        let texture_name = tileset.get_texture_name();
+        if texture_manager.get_texture_id(&texture_name).is_none() {
+            texture_manager.load_texture(&texture_name, &tileset.image).map_err(|e| format!("Error loading tileset image '{}': {}", tileset.image, e))?;
+        }
+    }
+
+    let mut importer = Importer {
+        map,
+        options,
+        used_names: HashSet::new(),
+        objects: Vec::new(),
+    };
+    importer.import_layers(&map.layers, (0.0, 0.0), true)?;
+
+    Ok(TiledImport {
+        objects: importer.objects,
+        properties: tiled_map::properties_to_map(&map.properties),
+    })
+}
+
+// The tiles of one layer drawn from one tileset
+struct TileMesh<'a> {
+    tileset: &'a TiledTileset,
+    vertices: Vec<f32>,
+    texture_coords: Vec<f32>,
+    cell_properties: Map<String, Value>, // Custom properties of each tile that has any, by "column,row"
+}
+
+struct Importer<'a> {
+    map: &'a TiledMap,
+    options: &'a TiledImportOptions,
+    used_names: HashSet<String>,
+    objects: Vec<ObjectData>,
+}
+
+impl Importer<'_> {
+    // Group layers pass their offset and visibility on to the layers inside them
+    fn import_layers(&mut self, layers: &[TiledLayer], offset: (f32, f32), visible: bool) -> Result<(), String> {
+        for layer in layers {
+            match layer {
+                TiledLayer::Tiles(layer) => self.import_tile_layer(layer, (offset.0 + layer.offsetx, offset.1 + layer.offsety), visible && layer.visible)?,
+                TiledLayer::Objects(layer) => {
+                    let layer_offset = (offset.0 + layer.offsetx, offset.1 + layer.offsety);
+                    for object in &layer.objects {
+                        self.import_object(object, &layer.name, layer_offset, visible && layer.visible)?;
+                    }
+                },
+                TiledLayer::Group(group) => self.import_layers(&group.layers, (offset.0 + group.offsetx, offset.1 + group.offsety), visible && group.visible)?,
+                TiledLayer::Unsupported => {},
+            }
+        }
+        Ok(())
+    }
+
+    fn import_tile_layer(&mut self, layer: &TileLayer, offset: (f32, f32), visible: bool) -> Result<(), String> {
+        let gids = layer.get_gids()?;
+
+        // One mesh per tileset, as each object draws with a single texture
+        let mut meshes: BTreeMap<u32, TileMesh> = BTreeMap::new();
+        for (index, &gid) in gids.iter().enumerate() {
+            if gid & GID_MASK == 0 {
+                continue; // Empty cell
+            }
+            let tileset = self.map.find_tileset(gid).ok_or_else(|| format!("Layer '{}' uses tile {} which is in no tileset", layer.name, gid & GID_MASK))?;
+            let mesh = meshes.entry(tileset.firstgid).or_insert_with(|| TileMesh { tileset, vertices: Vec::new(), texture_coords: Vec::new(), cell_properties: Map::new() });
+
+            let column = index as u32 % layer.width;
+            let row = index as u32 / layer.width;
+            let tile_properties = tileset.get_tile_properties((gid & GID_MASK) - tileset.firstgid);
+            if !tile_properties.is_empty() {
+                let properties = tiled_map::properties_to_map(tile_properties).into_iter().collect();
+                mesh.cell_properties.insert(format!("{},{}", column, row), Value::Object(properties));
+            }
+
+            // Tiles larger than the map grid are drawn up and to the right from the bottom left of their cell
+            let left = column as f32 * self.map.tilewidth as f32;
+            let bottom = (row + 1) as f32 * self.map.tileheight as f32;
+            let right = left + tileset.tilewidth as f32;
+            let top = bottom - tileset.tileheight as f32;
+
+            let corners = [(left, top), (right, top), (right, bottom), (left, bottom)];
+            let uvs = Self::corner_uvs(tileset, gid);
+            for corner in [0, 3, 2, 0, 2, 1] {
+                mesh.vertices.extend([corners[corner].0 / self.options.pixels_per_unit, -corners[corner].1 / self.options.pixels_per_unit]);
+                mesh.texture_coords.extend(uvs[corner]);
+            }
+        }
+
+        let several_tilesets = meshes.len() > 1;
+        for mesh in meshes.into_values() {
+            let base = if several_tilesets { format!("{}.{}", layer.name, mesh.tileset.name) } else { layer.name.clone() };
+            let name = self.unique_name(&base);
+
+            let mut properties = tiled_map::properties_to_map(&layer.properties);
+            if !mesh.cell_properties.is_empty() {
+                properties.insert(TILE_PROPERTIES_KEY.to_string(), Value::Object(mesh.cell_properties));
+            }
+
+            self.objects.push(ObjectData {
+                name,
+                vertex_data: mesh.vertices,
+                texture_coords: mesh.texture_coords,
+                shader: self.options.shader.clone(),
+                position: vec![offset.0 / self.options.pixels_per_unit, -offset.1 / self.options.pixels_per_unit, 0.0],
+                rotation: 0.0,
+                scale: 1.0,
+                texture_name: mesh.tileset.get_texture_name(),
+                collision_modes: Vec::new(),
+                properties,
+                draw_mode: Some("Triangles".to_string()),
+                visible: (!visible).then_some(false),
+            });
+        }
+        Ok(())
+    }
+
+    fn import_object(&mut self, object: &TiledObject, layer_name: &str, offset: (f32, f32), layer_visible: bool) -> Result<(), String> {
+        if object.text.is_some() {
+            return Ok(());
+        }
+
+        let mut properties = BTreeMap::new();
+        let mut texture_name = String::new();
+        let mut uvs = [[0.0, 0.0]; 4];
+        let mut shown = false;
+
+        // Outline in map pixels relative to the object's origin
+        let (outline, collision_modes): (Vec<(f32, f32)>, &[&str]) = if let Some(gid) = object.gid {
+            let tileset = self.map.find_tileset(gid).ok_or_else(|| format!("Object {} uses tile {} which is in no tileset", object.id, gid & GID_MASK))?;
+            properties.extend(tiled_map::properties_to_map(tileset.get_tile_properties((gid & GID_MASK) - tileset.firstgid)));
+            texture_name = tileset.get_texture_name();
+            uvs = Self::corner_uvs(tileset, gid);
+            shown = true;
+
+            // Tile objects are anchored at their bottom left
+            let width = if object.width > 0.0 { object.width } else { tileset.tilewidth as f32 };
+            let height = if object.height > 0.0 { object.height } else { tileset.tileheight as f32 };
+            (vec![(0.0, -height), (width, -height), (width, 0.0), (0.0, 0.0)], &["AABB"])
+        } else if object.point {
+            // A point has no area, so it becomes a marker that keeps its position and properties
+            (vec![(0.0, 0.0); 3], &[])
+        } else if object.ellipse {
+            let (radius_x, radius_y) = (object.width / 2.0, object.height / 2.0);
+            let outline = (0..ELLIPSE_SEGMENTS).map(|segment| {
+                let angle = segment as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                (radius_x + radius_x * angle.cos(), radius_y + radius_y * angle.sin())
+            }).collect();
+            (outline, &["Circle"])
+        } else if let Some(points) = &object.polygon {
+            (points.iter().map(|point| (point.x, point.y)).collect(), &["AABB"])
+        } else if let Some(points) = &object.polyline {
+            (points.iter().map(|point| (point.x, point.y)).collect(), &[])
+        } else {
+            let (width, height) = (object.width, object.height);
+            (vec![(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)], &["AABB"])
+        };
+        // Short polylines are padded out to the 3 vertices every object needs
+        let mut outline = outline;
+        while outline.len() < 3 {
+            outline.push(outline.last().copied().unwrap_or((0.0, 0.0)));
+        }
+
+        // Vertices are centred on the object's position so collision bounds line up
+        let (min_x, max_x) = outline.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), point| (min.min(point.0), max.max(point.0)));
+        let (min_y, max_y) = outline.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), point| (min.min(point.1), max.max(point.1)));
+        let center = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+        // Tiled rotates clockwise about the object's origin, with y pointing down
+        let angle = object.rotation.to_radians();
+        let rotated_center = (center.0 * angle.cos() - center.1 * angle.sin(), center.0 * angle.sin() + center.1 * angle.cos());
+        let ppu = self.options.pixels_per_unit;
+        let position = vec![(offset.0 + object.x + rotated_center.0) / ppu, -(offset.1 + object.y + rotated_center.1) / ppu, 0.0];
+
+        let vertex_data = outline.iter().flat_map(|point| [(point.0 - center.0) / ppu, -(point.1 - center.1) / ppu]).collect();
+        let texture_coords = if object.gid.is_some() {
+            uvs.iter().flatten().copied().collect()
+        } else {
+            vec![0.0; outline.len() * 2]
+        };
+
+        properties.extend(tiled_map::properties_to_map(&object.properties));
+        let class = if object.class.is_empty() { &object.kind } else { &object.class };
+        if !class.is_empty() {
+            properties.entry("class".to_string()).or_insert_with(|| Value::String(class.clone()));
+        }
+
+        let base = if object.name.is_empty() { format!("{}_{}", layer_name, object.id) } else { object.name.clone() };
+        let name = self.unique_name(&base);
+        self.objects.push(ObjectData {
+            name,
+            vertex_data,
+            texture_coords,
+            shader: self.options.shader.clone(),
+            position,
+            rotation: -angle,
+            scale: 1.0,
+            texture_name,
+            collision_modes: collision_modes.iter().map(|mode| mode.to_string()).collect(),
+            properties,
+            draw_mode: None,
+            visible: (!(shown && layer_visible && object.visible)).then_some(false),
+        });
+        Ok(())
+    }
+
+    // Texture coordinates for the top left, top right, bottom right and bottom left corners of a tile,
+    // with the tile's flip flags applied. Tiled flips diagonally first, then horizontally, then vertically.
+    fn corner_uvs(tileset: &TiledTileset, gid: u32) -> [[f32; 2]; 4] {
+        let [left, top, right, bottom] = tileset.tile_uv((gid & GID_MASK) - tileset.firstgid);
+        [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(mut x, mut y)| {
+            if gid & FLIPPED_VERTICALLY != 0 {
+                y = 1.0 - y;
+            }
+            if gid & FLIPPED_HORIZONTALLY != 0 {
+                x = 1.0 - x;
+            }
+            if gid & FLIPPED_DIAGONALLY != 0 {
+                (x, y) = (y, x);
+            }
+            [left + (right - left) * x, top + (bottom - top) * y]
+        })
+    }
+
+    // Tiled names need not be unique, later duplicates get a numbered suffix
+    fn unique_name(&mut self, base: &str) -> String {
+        let name = if self.used_names.contains(base) {
+            (1..).map(|index| format!("{}_{}", base, index)).find(|name| !self.used_names.contains(name)).unwrap()
+        } else {
+            base.to_string()
+        };
+        self.used_names.insert(name.clone());
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use serde_json::json;
+
+    use super::*;
+
+    // Writes a map into its own folder under the temp directory and loads it
+    fn load_map(test_name: &str, map: Value) -> (TiledMap, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("rusted_open_tiled_{}", test_name)).join("maps");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("level.tmj");
+        fs::write(&path, map.to_string()).unwrap();
+        (TiledMap::load(path.to_str().unwrap()).unwrap(), dir)
+    }
+
+    fn tileset(firstgid: u32, name: &str, image: &str) -> Value {
+        json!({
+            "firstgid": firstgid, "name": name, "image": image,
+            "tilewidth": 16, "tileheight": 16, "imagewidth": 32, "imageheight": 32, "columns": 2, "tilecount": 4
+        })
+    }
+
+    // Tile ids are already known to the texture manager, so importing needs no GL context
+    fn import(map: &TiledMap) -> TiledImport {
+        let texture_manager = TextureManager::new();
+        for (index, tileset) in map.tilesets.iter().enumerate() {
+            texture_manager.insert_texture_id(&tileset.get_texture_name(), index as u32 + 1);
+        }
+        import_tiled_map(map, &TiledImportOptions::default(), &texture_manager).unwrap()
+    }
+
+    #[test]
+    fn tilesets_sharing_an_image_name_get_their_own_textures() {
+        let (map, dir) = load_map("texture_names", json!({
+            "orientation": "orthogonal", "width": 2, "height": 1, "tilewidth": 16, "tileheight": 16,
+            "tilesets": [tileset(1, "grass", "./../art/grass/tiles.png"), tileset(5, "stone", "../art/stone/tiles.png")],
+            "layers": [{ "type": "tilelayer", "name": "ground", "width": 2, "height": 1, "data": [1, 5] }]
+        }));
+
+        let art_dir = dir.parent().unwrap().join("art");
+        let grass = map.tilesets[0].get_texture_name();
+        let stone = map.tilesets[1].get_texture_name();
+        assert_eq!(grass, art_dir.join("grass").join("tiles.png").to_string_lossy().replace('\\', "/"));
+        assert_eq!(stone, art_dir.join("stone").join("tiles.png").to_string_lossy().replace('\\', "/"));
+        assert_ne!(grass, "tiles");
+
+        let textures: HashSet<String> = import(&map).objects.into_iter().map(|object| object.texture_name).collect();
+        assert_eq!(textures, HashSet::from([grass, stone]));
+    }
+
+    #[test]
+    fn tile_layers_keep_tile_properties_per_cell() {
+        let mut walls = tileset(1, "walls", "walls.png");
+        walls["tiles"] = json!([{ "id": 1, "properties": [{ "name": "solid", "type": "bool", "value": true }] }]);
+        let (map, _) = load_map("tile_properties", json!({
+            "orientation": "orthogonal", "width": 3, "height": 2, "tilewidth": 16, "tileheight": 16,
+            "tilesets": [walls],
+            "layers": [{
+                "type": "tilelayer", "name": "walls", "width": 3, "height": 2, "data": [1, 2, 0, 0, 2, 1],
+                "properties": [{ "name": "depth", "type": "int", "value": 2 }]
+            }]
+        }));
+
+        let objects = import(&map).objects;
+        assert_eq!(objects.len(), 1);
+        let properties = &objects[0].properties;
+        assert_eq!(properties["depth"], json!(2));
+        assert_eq!(properties[TILE_PROPERTIES_KEY], json!({ "1,0": { "solid": true }, "1,1": { "solid": true } }));
+    }
+}
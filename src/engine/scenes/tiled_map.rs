@@ -0,0 +1,524 @@
+use std::{collections::BTreeMap, fs, io::Read, path::{Component, Path, PathBuf}, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use roxmltree::Node;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+// Tile ids in layers and tile objects carry flip flags in their top bits
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+pub const GID_MASK: u32 = !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120);
+
+fn default_visible() -> bool {
+    true
+}
+
+/// A custom property as Tiled writes it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TiledProperty {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Value,
+}
+
+/// Turns Tiled's list of properties into a map of name to value.
+pub fn properties_to_map(properties: &[TiledProperty]) -> BTreeMap<String, Value> {
+    properties.iter().map(|property| (property.name.clone(), property.value.clone())).collect()
+}
+
+/// A Tiled map as read from a .tmj or .tmx file. Field names follow the JSON format.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TiledMap {
+    pub width: u32, // In tiles
+    pub height: u32,
+    pub tilewidth: u32, // In pixels
+    pub tileheight: u32,
+    pub orientation: String,
+    pub infinite: bool,
+    pub properties: Vec<TiledProperty>,
+    pub tilesets: Vec<TiledTileset>,
+    pub layers: Vec<TiledLayer>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TiledTileset {
+    pub firstgid: u32,
+    pub source: Option<String>, // Set for tilesets kept in their own file
+    pub name: String,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    pub tilecount: u32,
+    pub columns: u32,
+    pub image: String, // Resolved to a path usable from the working directory once loaded
+    pub imagewidth: u32,
+    pub imageheight: u32,
+    pub tiles: Vec<TiledTile>,
+}
+
+/// Extra data for one tile of a tileset.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TiledTile {
+    pub id: u32,
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type")]
+pub enum TiledLayer {
+    #[serde(rename = "tilelayer")]
+    Tiles(TileLayer),
+    #[serde(rename = "objectgroup")]
+    Objects(ObjectLayer),
+    #[serde(rename = "group")]
+    Group(GroupLayer),
+    #[serde(other)]
+    Unsupported, // Image layers
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TileLayer {
+    pub name: String,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    pub offsetx: f32,
+    pub offsety: f32,
+    pub width: u32,
+    pub height: u32,
+    pub data: TileData,
+    pub encoding: String, // csv or base64 when the data is a string
+    pub compression: String, // Empty, zlib, gzip or zstd for base64 data
+    pub properties: Vec<TiledProperty>,
+}
+
+/// Layer data, either a list of tile ids or the encoded text Tiled wrote.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TileData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+impl Default for TileData {
+    fn default() -> Self {
+        TileData::Gids(Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ObjectLayer {
+    pub name: String,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    pub offsetx: f32,
+    pub offsety: f32,
+    pub objects: Vec<TiledObject>,
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GroupLayer {
+    pub name: String,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    pub offsetx: f32,
+    pub offsety: f32,
+    pub layers: Vec<TiledLayer>,
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String, // Called class since Tiled 1.9
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: f32, // Degrees clockwise
+    pub gid: Option<u32>, // Set for tile objects
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    pub ellipse: bool,
+    pub point: bool,
+    pub polygon: Option<Vec<TiledPoint>>,
+    pub polyline: Option<Vec<TiledPoint>>,
+    pub text: Option<Value>,
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct TiledPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl TiledMap {
+    /// Reads a .tmj or .tmx map and any external tilesets it uses. Only finite orthogonal maps are supported.
+    pub fn load(file_path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(file_path).map_err(|e| format!("Failed to read map '{}': {}", file_path, e))?;
+        let mut map = if has_extension(file_path, "tmx") {
+            parse_tmx_map(&text)?
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("Failed to parse map '{}': {}", file_path, e))?
+        };
+
+        if map.orientation != "orthogonal" {
+            return Err(format!("Map orientation '{}' is not supported, only orthogonal maps are", map.orientation));
+        }
+        if map.infinite {
+            return Err("Infinite maps are not supported".to_string());
+        }
+
+        let map_dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+        for tileset in &mut map.tilesets {
+            *tileset = tileset.resolve(map_dir)?;
+        }
+
+        Ok(map)
+    }
+
+    /// The tileset a tile id belongs to, flip flags are ignored.
+    pub fn find_tileset(&self, gid: u32) -> Option<&TiledTileset> {
+        let gid = gid & GID_MASK;
+        self.tilesets.iter().filter(|tileset| tileset.firstgid <= gid).max_by_key(|tileset| tileset.firstgid)
+    }
+}
+
+impl TiledTileset {
+    // Load an external tileset if this is a reference to one and make the image path usable
+    fn resolve(&self, map_dir: &Path) -> Result<Self, String> {
+        let (mut tileset, tileset_dir) = match &self.source {
+            Some(source) => {
+                let path = map_dir.join(source);
+                let path_str = path.to_string_lossy();
+                let text = fs::read_to_string(&path).map_err(|e| format!("Failed to read tileset '{}': {}", path_str, e))?;
+
+                let mut tileset = if has_extension(&path_str, "tsx") {
+                    let document = roxmltree::Document::parse(&text).map_err(|e| format!("Failed to parse tileset '{}': {}", path_str, e))?;
+                    parse_tmx_tileset(document.root_element())?
+                } else {
+                    serde_json::from_str::<TiledTileset>(&text).map_err(|e| format!("Failed to parse tileset '{}': {}", path_str, e))?
+                };
+                tileset.firstgid = self.firstgid;
+                tileset.source = None;
+                (tileset, path.parent().unwrap_or(Path::new("")).to_path_buf())
+            },
+            None => (self.clone(), map_dir.to_path_buf()),
+        };
+
+        if tileset.image.is_empty() {
+            return Err(format!("Tileset '{}' uses an image per tile, only tilesets made from one image are supported", tileset.name));
+        }
+        tileset.image = normalize_path(&tileset_dir.join(&tileset.image)).to_string_lossy().replace('\\', "/");
+
+        // Older files may leave out the column count
+        if tileset.columns == 0 && tileset.tilewidth > 0 {
+            tileset.columns = (tileset.imagewidth.saturating_sub(2 * tileset.margin) + tileset.spacing) / (tileset.tilewidth + tileset.spacing);
+        }
+
+        Ok(tileset)
+    }
+
+    /// Name the tileset image is loaded under in the texture manager, its resolved path. Images with the same
+    /// file name in different folders get their own textures, and cannot be mistaken for textures named by file stem.
+    pub fn get_texture_name(&self) -> String {
+        self.image.clone()
+    }
+
+    /// Texture coordinates of a tile as left, top, right and bottom.
+    pub fn tile_uv(&self, local_id: u32) -> [f32; 4] {
+        let columns = self.columns.max(1);
+        let left = self.margin + (local_id % columns) * (self.tilewidth + self.spacing);
+        let top = self.margin + (local_id / columns) * (self.tileheight + self.spacing);
+
+        let image_width = self.imagewidth.max(1) as f32;
+        let image_height = self.imageheight.max(1) as f32;
+        [
+            left as f32 / image_width,
+            top as f32 / image_height,
+            (left + self.tilewidth) as f32 / image_width,
+            (top + self.tileheight) as f32 / image_height,
+        ]
+    }
+
+    pub fn get_tile_properties(&self, local_id: u32) -> &[TiledProperty] {
+        self.tiles.iter().find(|tile| tile.id == local_id).map_or(&[], |tile| &tile.properties)
+    }
+}
+
+impl TileLayer {
+    /// Decodes the layer's tile ids, one per cell in rows from the top left.
+    pub fn get_gids(&self) -> Result<Vec<u32>, String> {
+        let gids = match &self.data {
+            TileData::Gids(gids) => gids.clone(),
+            TileData::Encoded(text) => match self.encoding.as_str() {
+                "csv" => text.split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.parse::<u32>().map_err(|_| format!("Invalid tile id '{}'", value)))
+                    .collect::<Result<_, _>>()?,
+                "base64" => decode_base64_gids(text, &self.compression)?,
+                encoding => return Err(format!("Unknown layer encoding '{}'", encoding)),
+            },
+        };
+
+        let expected = (self.width * self.height) as usize;
+        if gids.len() != expected {
+            return Err(format!("Layer '{}' has {} tiles, expected {}", self.name, gids.len(), expected));
+        }
+        Ok(gids)
+    }
+}
+
+fn decode_base64_gids(text: &str, compression: &str) -> Result<Vec<u32>, String> {
+    let bytes = STANDARD.decode(text.trim()).map_err(|e| format!("Invalid base64 layer data: {}", e))?;
+
+    let mut decompressed = Vec::new();
+    let bytes = match compression {
+        "" => bytes,
+        "zlib" => {
+            ZlibDecoder::new(&bytes[..]).read_to_end(&mut decompressed).map_err(|e| format!("Invalid zlib layer data: {}", e))?;
+            decompressed
+        },
+        "gzip" => {
+            GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed).map_err(|e| format!("Invalid gzip layer data: {}", e))?;
+            decompressed
+        },
+        compression => return Err(format!("Layer compression '{}' is not supported, use csv, uncompressed, zlib or gzip", compression)),
+    };
+
+    Ok(bytes.chunks_exact(4).map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]])).collect())
+}
+
+// Drop . and fold .. into the folder before it, so one image reached through different folders has one path
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path).extension().is_some_and(|found| found.eq_ignore_ascii_case(extension))
+}
+
+// Read an attribute, using the default when it is missing
+fn attribute<T: FromStr>(node: Node, name: &str, default: T) -> Result<T, String> {
+    match node.attribute(name) {
+        Some(value) => value.parse().map_err(|_| format!("Invalid value '{}' for '{}' on <{}>", value, name, node.tag_name().name())),
+        None => Ok(default),
+    }
+}
+
+fn text_attribute(node: Node, name: &str) -> String {
+    node.attribute(name).unwrap_or_default().to_string()
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+fn parse_tmx_map(text: &str) -> Result<TiledMap, String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| format!("Failed to parse map: {}", e))?;
+    let root = document.root_element();
+    if !root.has_tag_name("map") {
+        return Err("Expected a <map> element".to_string());
+    }
+
+    let mut map = TiledMap {
+        width: attribute(root, "width", 0)?,
+        height: attribute(root, "height", 0)?,
+        tilewidth: attribute(root, "tilewidth", 0)?,
+        tileheight: attribute(root, "tileheight", 0)?,
+        orientation: text_attribute(root, "orientation"),
+        infinite: attribute(root, "infinite", 0)? != 0,
+        properties: parse_tmx_properties(root)?,
+        ..Default::default()
+    };
+
+    for node in root.children().filter(Node::is_element) {
+        if node.has_tag_name("tileset") {
+            map.tilesets.push(parse_tmx_tileset(node)?);
+        } else if let Some(layer) = parse_tmx_layer(node)? {
+            map.layers.push(layer);
+        }
+    }
+
+    Ok(map)
+}
+
+fn parse_tmx_tileset(node: Node) -> Result<TiledTileset, String> {
+    let image = child(node, "image");
+    let mut tiles = Vec::new();
+    for tile in node.children().filter(|child| child.has_tag_name("tile")) {
+        tiles.push(TiledTile {
+            id: attribute(tile, "id", 0)?,
+            properties: parse_tmx_properties(tile)?,
+        });
+    }
+
+    Ok(TiledTileset {
+        firstgid: attribute(node, "firstgid", 1)?,
+        source: node.attribute("source").map(str::to_string),
+        name: text_attribute(node, "name"),
+        tilewidth: attribute(node, "tilewidth", 0)?,
+        tileheight: attribute(node, "tileheight", 0)?,
+        spacing: attribute(node, "spacing", 0)?,
+        margin: attribute(node, "margin", 0)?,
+        tilecount: attribute(node, "tilecount", 0)?,
+        columns: attribute(node, "columns", 0)?,
+        image: image.map(|image| text_attribute(image, "source")).unwrap_or_default(),
+        imagewidth: image.map_or(Ok(0), |image| attribute(image, "width", 0))?,
+        imageheight: image.map_or(Ok(0), |image| attribute(image, "height", 0))?,
+        tiles,
+    })
+}
+
+// Returns None for elements that are not layers and for image layers
+fn parse_tmx_layer(node: Node) -> Result<Option<TiledLayer>, String> {
+    let name = text_attribute(node, "name");
+    let visible = attribute(node, "visible", 1)? != 0;
+    let offsetx = attribute(node, "offsetx", 0.0)?;
+    let offsety = attribute(node, "offsety", 0.0)?;
+    let properties = parse_tmx_properties(node)?;
+
+    let layer = match node.tag_name().name() {
+        "layer" => {
+            let data = child(node, "data").ok_or_else(|| format!("Layer '{}' has no data", name))?;
+            if child(data, "chunk").is_some() {
+                return Err("Infinite maps are not supported".to_string());
+            }
+
+            let encoding = text_attribute(data, "encoding");
+            let data_value = if encoding.is_empty() {
+                let gids = data.children().filter(|child| child.has_tag_name("tile"))
+                    .map(|tile| attribute(tile, "gid", 0))
+                    .collect::<Result<_, _>>()?;
+                TileData::Gids(gids)
+            } else {
+                TileData::Encoded(data.text().unwrap_or_default().to_string())
+            };
+
+            TiledLayer::Tiles(TileLayer {
+                name,
+                visible,
+                offsetx,
+                offsety,
+                width: attribute(node, "width", 0)?,
+                height: attribute(node, "height", 0)?,
+                data: data_value,
+                encoding,
+                compression: text_attribute(data, "compression"),
+                properties,
+            })
+        },
+        "objectgroup" => {
+            let objects = node.children().filter(|child| child.has_tag_name("object"))
+                .map(parse_tmx_object)
+                .collect::<Result<_, _>>()?;
+            TiledLayer::Objects(ObjectLayer { name, visible, offsetx, offsety, objects, properties })
+        },
+        "group" => {
+            let mut layers = Vec::new();
+            for child in node.children().filter(Node::is_element) {
+                if let Some(layer) = parse_tmx_layer(child)? {
+                    layers.push(layer);
+                }
+            }
+            TiledLayer::Group(GroupLayer { name, visible, offsetx, offsety, layers, properties })
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(layer))
+}
+
+fn parse_tmx_object(node: Node) -> Result<TiledObject, String> {
+    let parse_points = |tag: &str| -> Result<Option<Vec<TiledPoint>>, String> {
+        let Some(shape) = child(node, tag) else {
+            return Ok(None);
+        };
+        let points = text_attribute(shape, "points");
+        points.split_whitespace().map(|point| {
+            let (x, y) = point.split_once(',').ok_or_else(|| format!("Invalid point '{}'", point))?;
+            Ok(TiledPoint {
+                x: x.parse().map_err(|_| format!("Invalid point '{}'", point))?,
+                y: y.parse().map_err(|_| format!("Invalid point '{}'", point))?,
+            })
+        }).collect::<Result<Vec<_>, String>>().map(Some)
+    };
+
+    Ok(TiledObject {
+        id: attribute(node, "id", 0)?,
+        name: text_attribute(node, "name"),
+        kind: text_attribute(node, "type"),
+        class: text_attribute(node, "class"),
+        x: attribute(node, "x", 0.0)?,
+        y: attribute(node, "y", 0.0)?,
+        width: attribute(node, "width", 0.0)?,
+        height: attribute(node, "height", 0.0)?,
+        rotation: attribute(node, "rotation", 0.0)?,
+        gid: node.attribute("gid").map(|_| attribute(node, "gid", 0)).transpose()?,
+        visible: attribute(node, "visible", 1)? != 0,
+        ellipse: child(node, "ellipse").is_some(),
+        point: child(node, "point").is_some(),
+        polygon: parse_points("polygon")?,
+        polyline: parse_points("polyline")?,
+        text: child(node, "text").map(|text| Value::String(text.text().unwrap_or_default().to_string())),
+        properties: parse_tmx_properties(node)?,
+    })
+}
+
+// Read the <properties> child of an element, converting values to JSON by their declared type
+fn parse_tmx_properties(node: Node) -> Result<Vec<TiledProperty>, String> {
+    let Some(properties) = child(node, "properties") else {
+        return Ok(Vec::new());
+    };
+
+    properties.children().filter(|child| child.has_tag_name("property")).map(|property| {
+        let name = text_attribute(property, "name");
+        let kind = text_attribute(property, "type");
+        // Multi-line strings are written as the element's text rather than an attribute
+        let raw = property.attribute("value").or_else(|| property.text()).unwrap_or_default();
+
+        let invalid = || format!("Invalid {} value '{}' for property '{}'", kind, raw, name);
+        let value = match kind.as_str() {
+            "int" | "object" => Value::from(raw.parse::<i64>().map_err(|_| invalid())?),
+            "float" => Value::from(raw.parse::<f64>().map_err(|_| invalid())?),
+            "bool" => Value::Bool(raw == "true"),
+            "class" => {
+                let members = parse_tmx_properties(property)?;
+                Value::Object(members.into_iter().map(|member| (member.name, member.value)).collect::<Map<_, _>>())
+            },
+            _ => Value::String(raw.to_string()),
+        };
+
+        Ok(TiledProperty { name, kind, value })
+    }).collect()
+}
@@ -0,0 +1,4 @@
+pub mod command_queue;
+pub mod engine_systems;
+pub mod scheduler;
+pub mod system_context;
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::sync::{Arc, Mutex};
+
+use glfw::CursorMode;
+
+use crate::engine::scenes::{background_loader::{LoadProgress, LoadRequest}, scene_data::PrefabInstance, scene_stack::SceneTransition};
+
+/// A change to the engine requested while the frame runs, applied by the event loop once the frame's systems are done.
+#[derive(Clone)]
+pub enum EngineCommand {
+    PushScene { name: String, transition: SceneTransition },
+    PopScene { transition: SceneTransition },
+    ReplaceScene { name: String, transition: SceneTransition },
+    UnloadScene(String),
+    SpawnPrefab(PrefabInstance),
+    LoadInBackground { request: LoadRequest, progress: LoadProgress },
+    SetCursorMode(CursorMode),
+    StartRebind { action: String, replace: bool },
+    StartAxisRebind { axis: String, positive: bool, replace: bool },
+    CancelRebind,
+    SetTextInputActive(bool),
+}
+
+/// Commands waiting for the end of the frame. Cheap to clone, so systems reach it through their context
+/// and event bus handlers can keep a clone from EventLoop::get_commands.
+#[derive(Clone, Default)]
+pub struct CommandQueue {
+    commands: Arc<Mutex<Vec<EngineCommand>>>,
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, command: EngineCommand) {
+        self.commands.lock().unwrap().push(command);
+    }
+
+    /// Takes every queued command in the order they were pushed.
+    pub fn drain(&self) -> Vec<EngineCommand> {
+        std::mem::take(&mut *self.commands.lock().unwrap())
+    }
+
+    /// Puts a loaded scene on top of the scene stack.
+    pub fn push_scene(&self, name: &str, transition: SceneTransition) {
+        self.push(EngineCommand::PushScene { name: name.to_string(), transition });
+    }
+
+    /// Takes the top scene off the scene stack.
+    pub fn pop_scene(&self, transition: SceneTransition) {
+        self.push(EngineCommand::PopScene { transition });
+    }
+
+    /// Swaps the top scene of the scene stack for another loaded scene.
+    pub fn replace_scene(&self, name: &str, transition: SceneTransition) {
+        self.push(EngineCommand::ReplaceScene { name: name.to_string(), transition });
+    }
+
+    /// Removes a scene and only its objects from the world and forgets it.
+    pub fn unload_scene(&self, name: &str) {
+        self.push(EngineCommand::UnloadScene(name.to_string()));
+    }
+
+    /// Creates a prefab instance in the world. Its objects are announced with ObjectSpawned events.
+    pub fn spawn_prefab(&self, instance: PrefabInstance) {
+        self.push(EngineCommand::SpawnPrefab(instance));
+    }
+
+    /// Starts a background load at the end of the frame. The returned handle reports its progress from then on.
+    pub fn load_in_background(&self, request: LoadRequest) -> LoadProgress {
+        let progress = LoadProgress::default();
+        self.push(EngineCommand::LoadInBackground { request, progress: progress.clone() });
+        progress
+    }
+
+    pub fn set_cursor_mode(&self, mode: CursorMode) {
+        self.push(EngineCommand::SetCursorMode(mode));
+    }
+
+    /// Binds the next pressed input to the action, see InputMap::start_rebind.
+    pub fn start_rebind(&self, action: &str, replace: bool) {
+        self.push(EngineCommand::StartRebind { action: action.to_string(), replace });
+    }
+
+    /// Binds the next pressed input to one direction of an axis, see InputMap::start_axis_rebind.
+    pub fn start_axis_rebind(&self, axis: &str, positive: bool, replace: bool) {
+        self.push(EngineCommand::StartAxisRebind { axis: axis.to_string(), positive, replace });
+    }
+
+    pub fn cancel_rebind(&self) {
+        self.push(EngineCommand::CancelRebind);
+    }
+
+    /// Starts or stops the text input taking typed characters.
+    pub fn set_text_input_active(&self, active: bool) {
+        self.push(EngineCommand::SetTextInputActive(active));
+    }
+}
@@ -0,0 +1,41 @@
+use crate::engine::events::{collision, event_bus::EngineEvent};
+
+use super::scheduler::{Stage, SystemDescriptor};
+
+pub const COLLISION_SYSTEM: &str = "collision";
+pub const DISPATCH_EVENTS_SYSTEM: &str = "dispatch_events";
+pub const CLEAR_SCREEN_SYSTEM: &str = "clear_screen";
+pub const DRAW_SYSTEM: &str = "draw";
+
+/// Checks the named object against every other object and publishes a Collision event per hit.
+pub fn collision_system(object_name: &str) -> SystemDescriptor {
+    let object_name = object_name.to_string();
+    SystemDescriptor::new(COLLISION_SYSTEM, Stage::PostUpdate, move |context| {
+        for event in collision::check_collisions(context.master_graphics_list, &object_name) {
+            context.event_bus.publish(EngineEvent::Collision(event));
+        }
+    })
+    .before(DISPATCH_EVENTS_SYSTEM)
+}
+
+/// Delivers the frame's queued events to subscribers once the update stages have published them.
+pub fn dispatch_events_system() -> SystemDescriptor {
+    SystemDescriptor::new(DISPATCH_EVENTS_SYSTEM, Stage::PostUpdate, |context| {
+        context.event_bus.dispatch();
+    })
+}
+
+/// Clears the screen to the given background color.
+pub fn clear_screen_system(red: f32, green: f32, blue: f32, alpha: f32) -> SystemDescriptor {
+    SystemDescriptor::new(CLEAR_SCREEN_SYSTEM, Stage::Render, move |context| {
+        context.viewport.clear(red, green, blue, alpha);
+    })
+}
+
+/// Draws every object in the master graphics list.
+pub fn draw_system() -> SystemDescriptor {
+    SystemDescriptor::new(DRAW_SYSTEM, Stage::Render, |context| {
+        context.master_graphics_list.draw_all(context.viewport.get_projection_matrix());
+    })
+    .after(CLEAR_SCREEN_SYSTEM)
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use super::system_context::SystemContext;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    PreUpdate,
+    FixedUpdate, // Runs zero or more times per frame at the fixed time step
+    Update,
+    PostUpdate,
+    Render,
+}
+
+impl Stage {
+    /// Every stage in the order it runs each frame.
+    pub const ALL: [Stage; 5] = [Stage::PreUpdate, Stage::FixedUpdate, Stage::Update, Stage::PostUpdate, Stage::Render];
+}
+
+pub type SystemFn = Box<dyn FnMut(&mut SystemContext)>;
+
+/// A named system along with the stage it runs in and its ordering constraints.
+pub struct SystemDescriptor {
+    name: String,
+    stage: Stage,
+    before: Vec<String>,
+    after: Vec<String>,
+    enabled: bool,
+    run: SystemFn,
+}
+
+impl SystemDescriptor {
+    pub fn new<F>(name: &str, stage: Stage, run: F) -> Self
+    where
+        F: FnMut(&mut SystemContext) + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            stage,
+            before: Vec::new(),
+            after: Vec::new(),
+            enabled: true,
+            run: Box::new(run),
+        }
+    }
+
+    /// Runs this system before the named system, which must be in the same stage.
+    pub fn before(mut self, name: &str) -> Self {
+        self.before.push(name.to_string());
+        self
+    }
+
+    /// Runs this system after the named system, which must be in the same stage.
+    pub fn after(mut self, name: &str) -> Self {
+        self.after.push(name.to_string());
+        self
+    }
+
+    /// Sets whether the system starts enabled.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_stage(&self) -> Stage {
+        self.stage
+    }
+}
+
+/// Runs registered systems stage by stage, honoring their before/after constraints.
+pub struct Scheduler {
+    systems: Vec<SystemDescriptor>, // Registration order, used to break ordering ties
+    order: HashMap<Stage, Vec<usize>>, // Execution order per stage as indices into systems
+    fixed_time_step: f32,
+    max_fixed_steps: u32, // Caps catch-up work after a long frame
+    accumulator: f32,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+            order: HashMap::new(),
+            fixed_time_step: 1.0 / 60.0,
+            max_fixed_steps: 5,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Registers a system. Fails if the name is taken, a constraint names a system that is not registered
+    /// in the same stage, or the constraints form a cycle. Systems must be added after those they name.
+    pub fn add_system(&mut self, system: SystemDescriptor) -> Result<(), String> {
+        if self.systems.iter().any(|existing| existing.name == system.name) {
+            return Err(format!("A system named '{}' is already registered", system.name));
+        }
+
+        self.systems.push(system);
+        if let Err(e) = self.rebuild_order() {
+            self.systems.pop();
+            self.rebuild_order()?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Removes a system by name. Returns false if it was not registered.
+    /// Fails, keeping the system, while other systems have ordering constraints on it.
+    pub fn remove_system(&mut self, name: &str) -> Result<bool, String> {
+        let Some(index) = self.systems.iter().position(|system| system.name == name) else {
+            return Ok(false);
+        };
+
+        let system = self.systems.remove(index);
+        if let Err(e) = self.rebuild_order() {
+            self.systems.insert(index, system);
+            self.rebuild_order()?;
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    pub fn set_system_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let system = self.systems.iter_mut()
+            .find(|system| system.name == name)
+            .ok_or_else(|| format!("No system named '{}'", name))?;
+        system.enabled = enabled;
+        Ok(())
+    }
+
+    /// Returns whether a system is enabled, or None if it is not registered.
+    pub fn is_system_enabled(&self, name: &str) -> Option<bool> {
+        self.systems.iter().find(|system| system.name == name).map(|system| system.enabled)
+    }
+
+    /// Lists the systems of a stage in the order they run.
+    pub fn list_systems(&self, stage: Stage) -> Vec<String> {
+        self.order.get(&stage)
+            .map(|order| order.iter().map(|&index| self.systems[index].name.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn set_fixed_time_step(&mut self, fixed_time_step: f32) {
+        self.fixed_time_step = fixed_time_step;
+    }
+
+    pub fn get_fixed_time_step(&self) -> f32 {
+        self.fixed_time_step
+    }
+
+    // Topologically sort each stage, taking systems in registration order whenever several are ready
+    fn rebuild_order(&mut self) -> Result<(), String> {
+        let mut order = HashMap::new();
+
+        for stage in Stage::ALL {
+            let members: Vec<usize> = (0..self.systems.len()).filter(|&i| self.systems[i].stage == stage).collect();
+            let position = |name: &str| members.iter().position(|&i| self.systems[i].name == name);
+
+            let resolve = |system: &SystemDescriptor, name: &str| {
+                position(name).ok_or_else(|| match self.systems.iter().find(|other| other.name == name) {
+                    Some(other) => format!("System '{}' is ordered against '{}', which runs in stage {:?} instead of {:?}", system.name, name, other.stage, stage),
+                    None => format!("System '{}' is ordered against '{}', which is not registered", system.name, name),
+                })
+            };
+
+            // Edges point from a system to the systems that must run after it
+            let mut successors = vec![Vec::new(); members.len()];
+            let mut remaining_predecessors = vec![0; members.len()];
+            for (local, &index) in members.iter().enumerate() {
+                let system = &self.systems[index];
+                for name in &system.before {
+                    let target = resolve(system, name)?;
+                    successors[local].push(target);
+                    remaining_predecessors[target] += 1;
+                }
+                for name in &system.after {
+                    let source = resolve(system, name)?;
+                    successors[source].push(local);
+                    remaining_predecessors[local] += 1;
+                }
+            }
+
+            let mut stage_order = Vec::with_capacity(members.len());
+            let mut done = vec![false; members.len()];
+            while stage_order.len() < members.len() {
+                let next = (0..members.len()).find(|&local| !done[local] && remaining_predecessors[local] == 0);
+                let Some(local) = next else {
+                    let cycle: Vec<&str> = (0..members.len())
+                        .filter(|&local| !done[local])
+                        .map(|local| self.systems[members[local]].name.as_str())
+                        .collect();
+                    return Err(format!("Systems in stage {:?} have cyclic ordering constraints: {}", stage, cycle.join(", ")));
+                };
+
+                done[local] = true;
+                stage_order.push(members[local]);
+                for &successor in &successors[local] {
+                    remaining_predecessors[successor] -= 1;
+                }
+            }
+
+            order.insert(stage, stage_order);
+        }
+
+        self.order = order;
+        Ok(())
+    }
+
+    /// Runs every enabled system of one stage once.
+    pub fn run_stage(&mut self, stage: Stage, context: &mut SystemContext) {
+        if let Some(order) = self.order.get(&stage) {
+            for &index in order {
+                let system = &mut self.systems[index];
+                if system.enabled {
+                    (system.run)(context);
+                }
+            }
+        }
+    }
+
+    /// Runs all stages for one frame. FixedUpdate runs once per elapsed fixed time step,
+    /// with the context's delta time set to the step while it runs.
+    pub fn run_frame(&mut self, context: &mut SystemContext) {
+        let frame_delta_time = context.delta_time;
+
+        self.run_stage(Stage::PreUpdate, context);
+
+        self.accumulator += frame_delta_time;
+        let mut steps = 0;
+        context.delta_time = self.fixed_time_step;
+        while self.accumulator >= self.fixed_time_step && steps < self.max_fixed_steps {
+            self.run_stage(Stage::FixedUpdate, context);
+            self.accumulator -= self.fixed_time_step;
+            steps += 1;
+        }
+        if steps == self.max_fixed_steps {
+            // Drop the backlog instead of spiraling further behind
+            self.accumulator = self.accumulator.min(self.fixed_time_step);
+        }
+        context.delta_time = frame_delta_time;
+
+        self.run_stage(Stage::Update, context);
+        self.run_stage(Stage::PostUpdate, context);
+        self.run_stage(Stage::Render, context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(name: &str, stage: Stage) -> SystemDescriptor {
+        SystemDescriptor::new(name, stage, |_| {})
+    }
+
+    #[test]
+    fn constraints_must_name_a_system_in_the_same_stage() {
+        let mut scheduler = Scheduler::new();
+        scheduler.add_system(system("draw", Stage::Render)).unwrap();
+
+        assert!(scheduler.add_system(system("missing", Stage::Update).before("nothing")).is_err());
+        assert!(scheduler.add_system(system("other_stage", Stage::Update).after("draw")).is_err());
+        assert_eq!(scheduler.list_systems(Stage::Update), Vec::<String>::new());
+
+        scheduler.add_system(system("clear", Stage::Render).before("draw")).unwrap();
+        assert_eq!(scheduler.list_systems(Stage::Render), vec!["clear", "draw"]);
+    }
+
+    #[test]
+    fn systems_named_by_constraints_cannot_be_removed() {
+        let mut scheduler = Scheduler::new();
+        scheduler.add_system(system("clear", Stage::Render)).unwrap();
+        scheduler.add_system(system("draw", Stage::Render).after("clear")).unwrap();
+
+        assert!(scheduler.remove_system("clear").is_err());
+        assert_eq!(scheduler.list_systems(Stage::Render), vec!["clear", "draw"]);
+
+        assert_eq!(scheduler.remove_system("draw"), Ok(true));
+        assert_eq!(scheduler.remove_system("draw"), Ok(false));
+        assert_eq!(scheduler.remove_system("clear"), Ok(true));
+        assert_eq!(scheduler.list_systems(Stage::Render), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,16 @@
+use crate::engine::{events::event_bus::EventBus, graphics::util::master_graphics_list::MasterGraphicsList, input::{combo::ComboRecognizer, input_map::InputMap, text_input::TextInput}, key_states::State, window::viewport::Viewport};
+
+use super::command_queue::CommandQueue;
+
+/// Everything a system can read or change while it runs.
+pub struct SystemContext<'a> {
+    pub master_graphics_list: &'a MasterGraphicsList,
+    pub state: &'a State,
+    pub input_map: &'a InputMap,
+    pub text_input: &'a TextInput,
+    pub combos: &'a ComboRecognizer,
+    pub event_bus: &'a mut EventBus,
+    pub viewport: &'a Viewport,
+    pub commands: &'a CommandQueue, // Scene changes, spawns, rebinds and other engine changes, applied after the frame
+    pub delta_time: f32, // Frame time in seconds, or the fixed time step during FixedUpdate
+}
@@ -0,0 +1,5 @@
+pub mod cursor;
+pub mod display;
+pub mod viewport;
+pub mod window_config;
+pub mod window_setup;
\ No newline at end of file
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use glfw::{Cursor, CursorMode, Glfw, PixelImage, StandardCursor, Window};
+
+use crate::engine::graphics::texture_manager::TextureManager;
+
+// A loaded cursor image, kept as pixels since GLFW cursors cannot be cloned
+struct CursorImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u32>,
+    hotspot: (u32, u32), // Click point in pixels from the top left
+}
+
+/// Which cursor the window is showing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursorShape {
+    Default,
+    Standard(StandardCursor),
+    Custom(String),
+}
+
+/// Controls the window's cursor image and whether it is visible or captured.
+pub struct CursorController {
+    images: HashMap<String, CursorImage>,
+    shape: CursorShape,
+    mode: CursorMode,
+}
+
+impl Default for CursorController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CursorController {
+    pub fn new() -> Self {
+        Self {
+            images: HashMap::new(),
+            shape: CursorShape::Default,
+            mode: CursorMode::Normal,
+        }
+    }
+
+    /// Loads a cursor image under a name, with the hotspot given in pixels from the top left.
+    pub fn load_cursor_image(&mut self, name: &str, path: &str, hotspot_x: u32, hotspot_y: u32) -> Result<(), String> {
+        let image = TextureManager::load_pixel_image(path)?;
+        if hotspot_x >= image.width || hotspot_y >= image.height {
+            return Err(format!("Cursor hotspot ({}, {}) is outside the {}x{} image '{}'", hotspot_x, hotspot_y, image.width, image.height, path));
+        }
+
+        self.images.insert(name.to_string(), CursorImage {
+            width: image.width,
+            height: image.height,
+            pixels: image.pixels,
+            hotspot: (hotspot_x, hotspot_y),
+        });
+        Ok(())
+    }
+
+    /// Shows a cursor loaded with load_cursor_image.
+    pub fn set_custom_cursor(&mut self, window: &mut Window, name: &str) -> Result<(), String> {
+        let image = self.images.get(name).ok_or_else(|| format!("Cursor '{}' is not loaded", name))?;
+
+        let pixel_image = PixelImage {
+            width: image.width,
+            height: image.height,
+            pixels: image.pixels.clone(),
+        };
+        window.set_cursor(Some(Cursor::create_from_pixels(pixel_image, image.hotspot.0, image.hotspot.1)));
+        self.shape = CursorShape::Custom(name.to_string());
+        Ok(())
+    }
+
+    /// Shows one of the system cursor shapes, such as the text I-beam or a resize arrow.
+    pub fn set_standard_cursor(&mut self, window: &mut Window, cursor: StandardCursor) {
+        window.set_cursor(Some(Cursor::standard(cursor)));
+        self.shape = CursorShape::Standard(cursor);
+    }
+
+    /// Goes back to the platform's default cursor.
+    pub fn reset_cursor(&mut self, window: &mut Window) {
+        window.set_cursor(None);
+        self.shape = CursorShape::Default;
+    }
+
+    pub fn get_shape(&self) -> &CursorShape {
+        &self.shape
+    }
+
+    /// Normal shows the cursor, Hidden hides it while over the window, and Disabled locks and hides it
+    /// for unlimited relative movement. Raw mouse motion is used while locked if the platform supports it.
+    pub fn set_cursor_mode(&mut self, glfw: &Glfw, window: &mut Window, mode: CursorMode) {
+        window.set_cursor_mode(mode);
+        if glfw.supports_raw_motion() {
+            window.set_raw_mouse_motion(mode == CursorMode::Disabled);
+        }
+        self.mode = mode;
+    }
+
+    pub fn get_cursor_mode(&self) -> CursorMode {
+        self.mode
+    }
+
+    pub fn is_cursor_locked(&self) -> bool {
+        self.mode == CursorMode::Disabled
+    }
+}
@@ -0,0 +1,207 @@
+use glfw::{Glfw, Monitor, PWindow, VidMode, WindowMode};
+
+use super::window_config::{DisplayMode, WindowConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoModeInfo {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+
+impl From<VidMode> for VideoModeInfo {
+    fn from(mode: VidMode) -> Self {
+        Self {
+            width: mode.width,
+            height: mode.height,
+            refresh_rate: mode.refresh_rate,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: String,
+    pub position: (i32, i32), // Position of the monitor on the virtual desktop
+    pub current_mode: Option<VideoModeInfo>,
+    pub video_modes: Vec<VideoModeInfo>,
+}
+
+/// Lists every connected monitor along with the video modes it supports.
+pub fn enumerate_monitors(glfw: &mut Glfw) -> Vec<MonitorInfo> {
+    glfw.with_connected_monitors(|_, monitors| {
+        monitors.iter().enumerate().map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.get_name().unwrap_or_else(|| format!("Monitor {}", index)),
+            position: monitor.get_pos(),
+            current_mode: monitor.get_video_mode().map(VideoModeInfo::from),
+            video_modes: monitor.get_video_modes().into_iter().map(VideoModeInfo::from).collect(),
+        }).collect()
+    })
+}
+
+/// Returns the distinct resolutions a monitor supports, largest first.
+pub fn monitor_resolutions(glfw: &mut Glfw, monitor_index: usize) -> Vec<(u32, u32)> {
+    let mut resolutions: Vec<(u32, u32)> = enumerate_monitors(glfw)
+        .get(monitor_index)
+        .map(|monitor| monitor.video_modes.iter().map(|mode| (mode.width, mode.height)).collect())
+        .unwrap_or_default();
+
+    resolutions.sort_by(|a, b| b.cmp(a));
+    resolutions.dedup();
+    resolutions
+}
+
+/// Switches a window between windowed, fullscreen and borderless modes and across monitors.
+pub struct DisplayController {
+    display_mode: DisplayMode,
+    monitor_index: usize,
+    windowed_rect: (i32, i32, i32, i32), // Position and size to restore when returning to windowed mode
+}
+
+impl DisplayController {
+    /// Takes the display mode and monitor the window was created from. An index with no monitor connected
+    /// falls back to the first monitor, as create_window does, so later mode changes target the same one.
+    /// A window created fullscreen or borderless returns to the configured size, centered on its monitor.
+    pub fn new(glfw: &mut Glfw, window: &PWindow, config: &WindowConfig) -> Self {
+        let monitors = enumerate_monitors(glfw);
+        let monitor_index = if config.monitor_index < monitors.len() { config.monitor_index } else { 0 };
+
+        let windowed_rect = match (config.display_mode, monitors.get(monitor_index)) {
+            (DisplayMode::Fullscreen | DisplayMode::Borderless, Some(monitor)) => {
+                Self::centered_rect(monitor, config.width as i32, config.height as i32)
+            },
+            _ => {
+                let (x, y) = window.get_pos();
+                let (width, height) = window.get_size();
+                (x, y, width, height)
+            },
+        };
+
+        Self {
+            display_mode: config.display_mode,
+            monitor_index,
+            windowed_rect,
+        }
+    }
+
+    pub fn get_display_mode(&self) -> DisplayMode {
+        self.display_mode
+    }
+
+    pub fn get_monitor_index(&self) -> usize {
+        self.monitor_index
+    }
+
+    /// Changes the display mode on the current monitor.
+    pub fn set_display_mode(&mut self, glfw: &mut Glfw, window: &mut PWindow, display_mode: DisplayMode) {
+        if display_mode == self.display_mode {
+            return;
+        }
+
+        // Remember where the window was so it can be restored later
+        if self.display_mode == DisplayMode::Windowed {
+            self.save_windowed_rect(window);
+        }
+
+        self.display_mode = display_mode;
+        self.apply(glfw, window);
+    }
+
+    /// Toggles between exclusive fullscreen and windowed mode.
+    pub fn toggle_fullscreen(&mut self, glfw: &mut Glfw, window: &mut PWindow) {
+        let display_mode = match self.display_mode {
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+            _ => DisplayMode::Fullscreen,
+        };
+        self.set_display_mode(glfw, window, display_mode);
+    }
+
+    /// Toggles between borderless fullscreen and windowed mode.
+    pub fn toggle_borderless(&mut self, glfw: &mut Glfw, window: &mut PWindow) {
+        let display_mode = match self.display_mode {
+            DisplayMode::Borderless => DisplayMode::Windowed,
+            _ => DisplayMode::Borderless,
+        };
+        self.set_display_mode(glfw, window, display_mode);
+    }
+
+    /// Moves the window to another monitor, keeping the current display mode.
+    pub fn move_to_monitor(&mut self, glfw: &mut Glfw, window: &mut PWindow, monitor_index: usize) -> Result<(), String> {
+        let monitors = enumerate_monitors(glfw);
+        let monitor = monitors.get(monitor_index).ok_or_else(|| format!("No monitor connected at index {}", monitor_index))?;
+
+        // Center the windowed rect on the new monitor, so returning to windowed mode stays there too
+        let (_, _, width, height) = self.windowed_rect;
+        self.windowed_rect = Self::centered_rect(monitor, width, height);
+
+        self.monitor_index = monitor_index;
+        self.apply(glfw, window);
+        Ok(())
+    }
+
+    /// Resizes the window, or changes the video mode when in exclusive fullscreen.
+    /// Borderless windows always match the monitor, so the request is ignored.
+    pub fn set_resolution(&mut self, glfw: &mut Glfw, window: &mut PWindow, width: u32, height: u32) {
+        match self.display_mode {
+            DisplayMode::Windowed => {
+                window.set_size(width as i32, height as i32);
+                self.save_windowed_rect(window);
+            },
+            DisplayMode::Fullscreen => {
+                let monitor_index = self.monitor_index;
+                glfw.with_connected_monitors(|_, monitors| {
+                    if let Some(monitor) = monitors.get(monitor_index) {
+                        window.set_monitor(WindowMode::FullScreen(monitor), 0, 0, width, height, None);
+                    }
+                });
+            },
+            DisplayMode::Borderless => {},
+        }
+    }
+
+    fn centered_rect(monitor: &MonitorInfo, width: i32, height: i32) -> (i32, i32, i32, i32) {
+        let (monitor_width, monitor_height) = monitor.current_mode
+            .map(|mode| (mode.width as i32, mode.height as i32))
+            .unwrap_or((width, height));
+        (
+            monitor.position.0 + (monitor_width - width) / 2,
+            monitor.position.1 + (monitor_height - height) / 2,
+            width,
+            height,
+        )
+    }
+
+    fn save_windowed_rect(&mut self, window: &PWindow) {
+        let (x, y) = window.get_pos();
+        let (width, height) = window.get_size();
+        self.windowed_rect = (x, y, width, height);
+    }
+
+    fn apply(&self, glfw: &mut Glfw, window: &mut PWindow) {
+        let display_mode = self.display_mode;
+        let monitor_index = self.monitor_index;
+        let (x, y, width, height) = self.windowed_rect;
+
+        glfw.with_connected_monitors(|_, monitors| {
+            let monitor: Option<&Monitor> = monitors.get(monitor_index).map(|monitor| &**monitor);
+            let video_mode = monitor.and_then(|monitor| monitor.get_video_mode());
+
+            match (display_mode, monitor, video_mode) {
+                (DisplayMode::Fullscreen, Some(monitor), Some(mode)) => {
+                    window.set_monitor(WindowMode::FullScreen(monitor), 0, 0, mode.width, mode.height, Some(mode.refresh_rate));
+                },
+                (DisplayMode::Borderless, Some(monitor), Some(mode)) => {
+                    let (monitor_x, monitor_y) = monitor.get_pos();
+                    window.set_decorated(false);
+                    window.set_monitor(WindowMode::Windowed, monitor_x, monitor_y, mode.width, mode.height, None);
+                },
+                _ => {
+                    window.set_decorated(true);
+                    window.set_monitor(WindowMode::Windowed, x, y, width as u32, height as u32, None);
+                },
+            }
+        });
+    }
+}
@@ -0,0 +1,177 @@
+use nalgebra::{Matrix4, Vector2, Vector4};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScalingPolicy {
+    Stretch,   // Fill the framebuffer with the virtual view, distorting it if the aspect ratio differs
+    Letterbox, // Keep the virtual aspect ratio and fill the leftover space with bars
+    Expand,    // Fill the framebuffer and reveal more of the world along the longer axis
+}
+
+/// Tracks the framebuffer size and derives the GL viewport and projection matrix from it.
+pub struct Viewport {
+    policy: ScalingPolicy,
+    virtual_width: f32,
+    virtual_height: f32,
+    framebuffer_size: (i32, i32),
+    window_size: (i32, i32), // Screen coordinates, which differ from framebuffer pixels on HiDPI displays
+    rect: (i32, i32, i32, i32), // x, y, width, height in framebuffer pixels
+    projection_matrix: Matrix4<f32>,
+}
+
+impl Viewport {
+    pub fn new(policy: ScalingPolicy, virtual_width: u32, virtual_height: u32, framebuffer_width: i32, framebuffer_height: i32) -> Self {
+        let mut viewport = Self {
+            policy,
+            virtual_width: virtual_width.max(1) as f32,
+            virtual_height: virtual_height.max(1) as f32,
+            framebuffer_size: (0, 0),
+            window_size: (framebuffer_width, framebuffer_height),
+            rect: (0, 0, 0, 0),
+            projection_matrix: Matrix4::identity(),
+        };
+        viewport.resize(framebuffer_width, framebuffer_height);
+        viewport
+    }
+
+    /// Recalculates the viewport rectangle and projection for a new framebuffer size.
+    /// Sizes of zero (a minimized window) are ignored so the last valid state is kept.
+    pub fn resize(&mut self, framebuffer_width: i32, framebuffer_height: i32) {
+        if framebuffer_width <= 0 || framebuffer_height <= 0 {
+            return;
+        }
+        self.framebuffer_size = (framebuffer_width, framebuffer_height);
+
+        let virtual_aspect = self.virtual_width / self.virtual_height;
+        let actual_aspect = framebuffer_width as f32 / framebuffer_height as f32;
+
+        match self.policy {
+            ScalingPolicy::Stretch => {
+                self.rect = (0, 0, framebuffer_width, framebuffer_height);
+                self.projection_matrix = Self::orthographic(1.0, 1.0 / virtual_aspect);
+            },
+            ScalingPolicy::Letterbox => {
+                // Fit the largest rectangle with the virtual aspect ratio and center it
+                let (width, height) = if actual_aspect > virtual_aspect {
+                    ((framebuffer_height as f32 * virtual_aspect).round() as i32, framebuffer_height) // Pillarbox
+                } else {
+                    (framebuffer_width, (framebuffer_width as f32 / virtual_aspect).round() as i32) // Letterbox
+                };
+                self.rect = ((framebuffer_width - width) / 2, (framebuffer_height - height) / 2, width, height);
+                self.projection_matrix = Self::orthographic(1.0, 1.0 / virtual_aspect);
+            },
+            ScalingPolicy::Expand => {
+                self.rect = (0, 0, framebuffer_width, framebuffer_height);
+                self.projection_matrix = if actual_aspect >= virtual_aspect {
+                    Self::orthographic(actual_aspect / virtual_aspect, 1.0 / virtual_aspect)
+                } else {
+                    Self::orthographic(1.0, 1.0 / actual_aspect)
+                };
+            },
+        }
+    }
+
+    // Orthographic projection centered on the origin with the given half extents
+    fn orthographic(half_width: f32, half_height: f32) -> Matrix4<f32> {
+        Matrix4::new_orthographic(-half_width, half_width, -half_height, half_height, -1.0, 1.0)
+    }
+
+    /// Clears the framebuffer, painting any bars black, and applies the viewport rectangle.
+    pub fn clear(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        let (x, y, width, height) = self.rect;
+        unsafe {
+            gl::Viewport(0, 0, self.framebuffer_size.0, self.framebuffer_size.1);
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            // Only clear the visible area to the background color
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(x, y, width, height);
+            gl::ClearColor(red, green, blue, alpha);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::Disable(gl::SCISSOR_TEST);
+
+            gl::Viewport(x, y, width, height);
+        }
+    }
+
+    /// Records the window size in screen coordinates, used to convert cursor positions.
+    pub fn set_window_size(&mut self, window_width: i32, window_height: i32) {
+        if window_width > 0 && window_height > 0 {
+            self.window_size = (window_width, window_height);
+        }
+    }
+
+    /// Converts a position in window screen coordinates (origin top left) to world coordinates
+    /// using the current viewport rectangle and projection. Gives the origin while the window has no size,
+    /// such as when it starts minimized.
+    pub fn screen_to_world(&self, x: f64, y: f64) -> Vector2<f32> {
+        let (rect_x, rect_y, rect_width, rect_height) = self.rect;
+        if rect_width <= 0 || rect_height <= 0 || self.window_size.0 <= 0 || self.window_size.1 <= 0 {
+            return Vector2::zeros();
+        }
+
+        // Scale screen coordinates to framebuffer pixels and flip to OpenGL's bottom left origin
+        let scale_x = self.framebuffer_size.0 as f32 / self.window_size.0 as f32;
+        let scale_y = self.framebuffer_size.1 as f32 / self.window_size.1 as f32;
+        let pixel_x = x as f32 * scale_x;
+        let pixel_y = (self.window_size.1 as f32 - y as f32) * scale_y;
+
+        // Map the viewport rectangle to normalized device coordinates
+        let ndc_x = (pixel_x - rect_x as f32) / rect_width as f32 * 2.0 - 1.0;
+        let ndc_y = (pixel_y - rect_y as f32) / rect_height as f32 * 2.0 - 1.0;
+
+        let inverse_projection = self.projection_matrix.try_inverse().unwrap_or_else(Matrix4::identity);
+        let world = inverse_projection * Vector4::new(ndc_x, ndc_y, 0.0, 1.0);
+        Vector2::new(world.x, world.y)
+    }
+
+    pub fn set_policy(&mut self, policy: ScalingPolicy) {
+        self.policy = policy;
+        self.resize(self.framebuffer_size.0, self.framebuffer_size.1);
+    }
+
+    pub fn set_virtual_resolution(&mut self, virtual_width: u32, virtual_height: u32) {
+        self.virtual_width = virtual_width.max(1) as f32;
+        self.virtual_height = virtual_height.max(1) as f32;
+        self.resize(self.framebuffer_size.0, self.framebuffer_size.1);
+    }
+
+    pub fn get_policy(&self) -> ScalingPolicy {
+        self.policy
+    }
+
+    pub fn get_framebuffer_size(&self) -> (i32, i32) {
+        self.framebuffer_size
+    }
+
+    pub fn get_window_size(&self) -> (i32, i32) {
+        self.window_size
+    }
+
+    pub fn get_rect(&self) -> (i32, i32, i32, i32) {
+        self.rect
+    }
+
+    pub fn get_projection_matrix(&self) -> &Matrix4<f32> {
+        &self.projection_matrix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_without_a_size_map_the_cursor_to_the_origin() {
+        let mut viewport = Viewport::new(ScalingPolicy::Stretch, 800, 600, 0, 0);
+        viewport.set_window_size(0, 0);
+        assert_eq!(viewport.screen_to_world(120.0, 80.0), Vector2::zeros());
+
+        // Once restored, the center of the window is the center of the world
+        viewport.resize(800, 600);
+        viewport.set_window_size(800, 600);
+        let center = viewport.screen_to_world(400.0, 300.0);
+        assert!(center.x.abs() < 1e-4 && center.y.abs() < 1e-4, "{:?}", center);
+    }
+}
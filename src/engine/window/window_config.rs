@@ -0,0 +1,128 @@
+use std::{fs::File, io::{self, Read}};
+
+use serde::{Deserialize, Serialize};
+
+use super::viewport::ScalingPolicy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    Fullscreen, // Exclusive fullscreen on the selected monitor
+    Borderless, // Undecorated window covering the selected monitor
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)] // Any field missing from the file falls back to its default
+pub struct WindowConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub resizable: bool,
+    pub swap_interval: u32, // 0 disables vsync, 1 syncs every frame, N syncs every N frames
+    pub max_fps: Option<f32>, // Software frame cap, None leaves the frame rate uncapped
+    pub display_mode: DisplayMode,
+    pub monitor_index: usize, // Index into the list of connected monitors
+    pub msaa_samples: Option<u32>, // None disables multisampling
+    pub icon_path: Option<String>,
+    pub scaling_policy: ScalingPolicy,
+    pub virtual_resolution: Option<(u32, u32)>, // Resolution the scene is authored for, defaults to the window size
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Rusted-OpenGL".to_string(),
+            width: 1280,
+            height: 720,
+            resizable: true,
+            swap_interval: 1,
+            max_fps: None,
+            display_mode: DisplayMode::Windowed,
+            monitor_index: 0,
+            msaa_samples: None,
+            icon_path: None,
+            scaling_policy: ScalingPolicy::Expand,
+            virtual_resolution: None,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Creates a config with the default window settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a config from a JSON file, using defaults for any missing fields.
+    pub fn load_from_json(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(file_path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        let config: WindowConfig = serde_json::from_str(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(config)
+    }
+
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn with_swap_interval(mut self, swap_interval: u32) -> Self {
+        self.swap_interval = swap_interval;
+        self
+    }
+
+    pub fn with_max_fps(mut self, max_fps: Option<f32>) -> Self {
+        self.max_fps = max_fps;
+        self
+    }
+
+    pub fn with_display_mode(mut self, display_mode: DisplayMode) -> Self {
+        self.display_mode = display_mode;
+        self
+    }
+
+    pub fn with_monitor_index(mut self, monitor_index: usize) -> Self {
+        self.monitor_index = monitor_index;
+        self
+    }
+
+    pub fn with_msaa_samples(mut self, msaa_samples: Option<u32>) -> Self {
+        self.msaa_samples = msaa_samples;
+        self
+    }
+
+    pub fn with_icon_path(mut self, icon_path: &str) -> Self {
+        self.icon_path = Some(icon_path.to_string());
+        self
+    }
+
+    pub fn with_scaling_policy(mut self, scaling_policy: ScalingPolicy) -> Self {
+        self.scaling_policy = scaling_policy;
+        self
+    }
+
+    pub fn with_virtual_resolution(mut self, width: u32, height: u32) -> Self {
+        self.virtual_resolution = Some((width, height));
+        self
+    }
+
+    /// Returns the virtual resolution, falling back to the initial window size.
+    pub fn get_virtual_resolution(&self) -> (u32, u32) {
+        self.virtual_resolution.unwrap_or((self.width, self.height))
+    }
+}
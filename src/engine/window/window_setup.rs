@@ -0,0 +1,48 @@
+use glfw::{Context, Glfw, GlfwReceiver, PWindow, WindowEvent, WindowHint, WindowMode};
+
+use crate::engine::graphics::texture_manager::TextureManager;
+
+use super::window_config::{DisplayMode, WindowConfig};
+
+/// Creates the GLFW window described by the config and makes its context current.
+pub fn create_window(glfw: &mut Glfw, config: &WindowConfig) -> (PWindow, GlfwReceiver<(f64, WindowEvent)>) {
+    glfw.window_hint(WindowHint::Resizable(config.resizable));
+    glfw.window_hint(WindowHint::Samples(config.msaa_samples));
+    glfw.window_hint(WindowHint::Decorated(config.display_mode != DisplayMode::Borderless));
+
+    let (mut window, events) = glfw.with_connected_monitors(|glfw, monitors| {
+        // Fall back to the first monitor if the requested one is not connected
+        let monitor = monitors.get(config.monitor_index).or(monitors.first());
+
+        match (config.display_mode, monitor) {
+            (DisplayMode::Fullscreen, Some(monitor)) => {
+                glfw.create_window(config.width, config.height, &config.title, WindowMode::FullScreen(monitor))
+            },
+            (DisplayMode::Borderless, Some(monitor)) => {
+                // Cover the whole monitor at its current video mode
+                let (width, height) = monitor.get_video_mode()
+                    .map(|mode| (mode.width, mode.height))
+                    .unwrap_or((config.width, config.height));
+                let (monitor_x, monitor_y) = monitor.get_pos();
+
+                glfw.create_window(width, height, &config.title, WindowMode::Windowed)
+                    .map(|(mut window, events)| {
+                        window.set_pos(monitor_x, monitor_y);
+                        (window, events)
+                    })
+            },
+            _ => glfw.create_window(config.width, config.height, &config.title, WindowMode::Windowed),
+        }
+    }).expect("Failed to create GLFW window.");
+
+    // Make the window's context current
+    window.make_current();
+
+    if let Some(icon_path) = &config.icon_path {
+        if let Err(e) = TextureManager::load_pixel_image(icon_path).map(|icon| window.set_icon_from_pixels(vec![icon])) {
+            println!("Failed to load window icon '{}': {}", icon_path, e);
+        }
+    }
+
+    (window, events)
+}
@@ -1,6 +1,42 @@
-mod engine;
+use rusted_open::engine::{input::{combo::ComboRecognizer, input_map::InputMap, input_recording::InputRecording}, window::window_config::WindowConfig};
 
 fn main() {
-    let mut event_loop = engine::event_loop::EventLoop::new();
+    // --record <file> saves this session's input on exit, --replay <file> plays a saved session back
+    let args: Vec<String> = std::env::args().collect();
+    let arg_value = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1)).cloned();
+    let record_path = arg_value("--record");
+    let replay_path = arg_value("--replay");
+
+    let window_config = WindowConfig::load_from_json("./src/resources/config/window.json").unwrap_or_else(|e| {
+        println!("Using default window config: {}", e);
+        WindowConfig::default()
+    });
+
+    let mut event_loop = rusted_open::engine::event_loop::EventLoop::new(window_config);
+    match InputMap::load_from_json("./src/resources/config/input_map.json") {
+        Ok(input_map) => event_loop.set_input_map(input_map),
+        Err(e) => println!("Using default input map: {}", e),
+    }
+    match ComboRecognizer::load_from_json("./src/resources/config/combos.json") {
+        Ok(combos) => event_loop.set_combos(combos),
+        Err(e) => println!("No combos loaded: {}", e),
+    }
+
+    if let Some(path) = &replay_path {
+        match InputRecording::load_from_json(path) {
+            Ok(recording) => event_loop.start_replay(recording),
+            Err(e) => println!("Failed to load input recording '{}': {}", path, e),
+        }
+    }
+    if record_path.is_some() {
+        event_loop.start_recording();
+    }
+
     event_loop.run_event_loop();
+
+    if let (Some(path), Some(recording)) = (&record_path, event_loop.stop_recording()) {
+        if let Err(e) = recording.save_to_json(path) {
+            println!("Failed to save input recording '{}': {}", path, e);
+        }
+    }
 }
@@ -0,0 +1,11 @@
+#version 330 core
+out vec4 color;
+in vec2 TexCoord;
+
+uniform sampler2D texture1;
+uniform float opacity = 1.0;
+
+void main() {
+    color = texture(texture1, TexCoord);
+    color.a *= opacity;
+}
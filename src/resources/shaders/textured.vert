@@ -0,0 +1,11 @@
+#version 330 core
+layout(location = 0) in vec2 aPos;
+layout(location = 1) in vec2 aTexCoord; // Add this line to receive texture coordinates
+out vec2 TexCoord; // Add this line to output texture coordinates
+uniform mat4 model;
+uniform mat4 projection;
+
+void main() {
+    gl_Position = projection * model * vec4(aPos, 0.0, 1.0);
+    TexCoord = aTexCoord; // Pass the texture coordinates to the fragment shader
+}